@@ -0,0 +1,90 @@
+//! Plays bots against each other without the app, every pair of them in a round robin.
+//! eg. `cargo run --release --bin arena -- --hands 100000 --duplicate Random '{"Tag": {}}'`
+
+use poker::game::{
+    arena::{self, ArenaCfg, Estimate},
+    bots::BotCfg,
+};
+use std::{env, fs::File, process};
+
+const USAGE: &str = "Usage: arena [--hands N] [--seed N] [--duplicate] [--all-in-samples N] \
+[--config PATH] BOT BOT...
+BOT is a bot config in JSON, eg. CallingStation or '{\"Tag\": {}}'";
+
+/// parses the value of an option
+fn parse<T: std::str::FromStr>(option: &str, value: Option<String>) -> Result<T, String> {
+    value
+        .and_then(|value| value.parse().ok())
+        .ok_or(format!("{} needs a number", option))
+}
+
+/// bots can be plain names like Random instead of JSON strings
+fn parse_bot(s: &str) -> Result<BotCfg, String> {
+    serde_json::from_str(s)
+        .or_else(|_| serde_json::from_value(serde_json::Value::String(s.into())))
+        .map_err(|e| format!("Can't parse bot {}: {}", s, e))
+}
+
+fn format_estimate(estimate: Estimate) -> String {
+    format!(
+        "{:.1} ± {:.1} mbb/hand",
+        estimate.get_mean(),
+        estimate.get_margin()
+    )
+}
+
+fn run() -> Result<(), String> {
+    let mut hands = 10_000;
+    let mut seed = 0;
+    let mut duplicate = false;
+    let mut all_in_samples = 10_000;
+    let mut config = String::from("../poker.conf.json");
+    let mut bots = Vec::new();
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--hands" => hands = parse(&arg, args.next())?,
+            "--seed" => seed = parse(&arg, args.next())?,
+            "--duplicate" => duplicate = true,
+            "--all-in-samples" => all_in_samples = parse(&arg, args.next())?,
+            "--config" => config = args.next().ok_or("--config needs a path")?,
+            "--help" => return Err(USAGE.into()),
+            _ => bots.push((arg.clone(), parse_bot(&arg)?)),
+        }
+    }
+    if bots.len() < 2 {
+        return Err(USAGE.into());
+    }
+    let file = File::open(&config).map_err(|e| format!("Can't read {}: {}", config, e))?;
+    let game =
+        serde_json::from_reader(file).map_err(|e| format!("Can't parse {}: {}", config, e))?;
+    let cfg = ArenaCfg {
+        game,
+        hands,
+        seed,
+        duplicate,
+        all_in_samples,
+    };
+
+    for (i, (first_name, first)) in bots.iter().enumerate() {
+        for (second_name, second) in bots.iter().skip(i + 1) {
+            let result = arena::play_match(&cfg, [first, second])?;
+            println!(
+                "{} vs {}, {} hands: {} (all-in adjusted {})",
+                first_name,
+                second_name,
+                result.get_hands(),
+                format_estimate(result.get_winnings()),
+                format_estimate(result.get_all_in_adjusted())
+            );
+        }
+    }
+    Ok(())
+}
+
+fn main() {
+    if let Err(e) = run() {
+        eprintln!("{}", e);
+        process::exit(1);
+    }
+}
@@ -0,0 +1,106 @@
+//! Trains a blueprint for heads-up no-limit hold'em with Monte Carlo CFR, for the Blueprint bot.
+//! eg. `cargo run --release --bin train -- --iterations 10000000 --out blueprint.bin`
+//! saves a checkpoint every so often and resumes from it when it's run again
+
+use poker::game::{
+    blueprint::Blueprint,
+    holdem_tree::{HoldemTree, HoldemTreeCfg},
+    mccfr::{Mccfr, MccfrCfg, Sampling},
+};
+use std::{env, fs::File, path::Path, process, time::Instant};
+
+const USAGE: &str = "Usage: train [--iterations N] [--threads N] [--seed N] \
+[--sampling external|outcome] [--exploration F] [--checkpoint PATH] [--checkpoint-every N] \
+[--config PATH] [--out PATH]
+--config is a JSON abstraction, see HoldemTreeCfg. resuming needs the same config";
+
+/// parses the value of an option
+fn parse<T: std::str::FromStr>(option: &str, value: Option<String>) -> Result<T, String> {
+    value
+        .and_then(|value| value.parse().ok())
+        .ok_or(format!("{} needs a number", option))
+}
+
+fn run() -> Result<(), String> {
+    let mut iterations = 1_000_000;
+    let mut checkpoint_every = 100_000;
+    let mut exploration = 0.6;
+    let mut sampling = String::from("external");
+    let mut checkpoint = String::from("blueprint.ckpt");
+    let mut config = None;
+    let mut out = String::from("blueprint.bin");
+    let mut mccfr_cfg = MccfrCfg::default();
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--iterations" => iterations = parse(&arg, args.next())?,
+            "--threads" => mccfr_cfg.threads = parse(&arg, args.next())?,
+            "--seed" => mccfr_cfg.seed = parse(&arg, args.next())?,
+            "--sampling" => sampling = args.next().ok_or("--sampling needs a method")?,
+            "--exploration" => exploration = parse(&arg, args.next())?,
+            "--checkpoint" => checkpoint = args.next().ok_or("--checkpoint needs a path")?,
+            "--checkpoint-every" => checkpoint_every = parse(&arg, args.next())?,
+            "--config" => config = Some(args.next().ok_or("--config needs a path")?),
+            "--out" => out = args.next().ok_or("--out needs a path")?,
+            _ => return Err(USAGE.into()),
+        }
+    }
+    mccfr_cfg.sampling = match sampling.as_str() {
+        "external" => Sampling::External,
+        "outcome" => Sampling::Outcome { exploration },
+        _ => return Err(format!("{} isn't a sampling method\n{}", sampling, USAGE)),
+    };
+    if checkpoint_every == 0 {
+        return Err("--checkpoint-every has to be at least 1".into());
+    }
+    let tree_cfg: HoldemTreeCfg = match &config {
+        Some(config) => {
+            let file = File::open(config).map_err(|e| format!("Can't read {}: {}", config, e))?;
+            serde_json::from_reader(file).map_err(|e| format!("Can't parse {}: {}", config, e))?
+        }
+        None => HoldemTreeCfg::default(),
+    };
+    let tree = HoldemTree::new(tree_cfg)?;
+
+    let checkpoint = Path::new(&checkpoint);
+    let mut mccfr = if checkpoint.exists() {
+        let mccfr = Mccfr::load(tree, mccfr_cfg, checkpoint)?;
+        println!(
+            "Resuming from {:?} after {} iterations",
+            checkpoint,
+            mccfr.get_iterations()
+        );
+        mccfr
+    } else {
+        Mccfr::new(tree, mccfr_cfg)?
+    };
+    while mccfr.get_iterations() < iterations {
+        let start = Instant::now();
+        let chunk = checkpoint_every.min(iterations - mccfr.get_iterations());
+        mccfr.iterate(chunk);
+        mccfr.save(checkpoint)?;
+        println!(
+            "{} iterations, {} info sets, {:.0} iterations/s, average regret {:.5}",
+            mccfr.get_iterations(),
+            mccfr.get_num_info_sets(),
+            chunk as f64 / start.elapsed().as_secs_f64(),
+            mccfr.get_average_regret()
+        );
+    }
+    let blueprint = Blueprint::new(&mccfr)?;
+    blueprint.save(Path::new(&out))?;
+    println!(
+        "Saved {} info sets to {} after {} iterations",
+        blueprint.get_num_info_sets(),
+        out,
+        blueprint.get_iterations()
+    );
+    Ok(())
+}
+
+fn main() {
+    if let Err(e) = run() {
+        eprintln!("{}", e);
+        process::exit(1);
+    }
+}
@@ -0,0 +1,552 @@
+//! Card abstraction, groups hands with similar strength into buckets.
+//! Hands are described by their expected hand strength (EHS), EHS² or
+//! their distribution of hand strength on the river, then clustered with k-means.
+
+use std::{
+    fs::File,
+    io::{BufReader, BufWriter, Read, Write},
+    path::Path,
+};
+
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use super::card::{evaluate, Card, CardSet, HandClass, HandIndexer, NUM_CLASSES};
+use super::BettingRound;
+
+/// start of a bucket table file
+const MAGIC: &[u8; 4] = b"BKTS";
+/// version of the bucket table file, bumped when the format or the features change
+const TABLE_VERSION: u32 = 1;
+
+/// FNV-1a of a sequence of values, unlike the std hasher it's the same in every build so
+/// it can be written to files
+pub fn fnv_hash<I: IntoIterator<Item = u64>>(values: I) -> u64 {
+    values
+        .into_iter()
+        .fold(0xcbf2_9ce4_8422_2325, |hash, value| {
+            (hash ^ value).wrapping_mul(0x0100_0000_01b3)
+        })
+}
+
+/// Fraction of the opponent's possible hands that the hole beats on the current board,
+/// ties count as half
+pub fn hand_strength(hole: CardSet, board: CardSet) -> f64 {
+    let strength = evaluate(hole | board);
+    let mut score = 0.0;
+    let mut total = 0.0;
+    (!(hole | board)).for_each_subset(2, &mut |opponent| {
+        let opponent_strength = evaluate(opponent | board);
+        if strength > opponent_strength {
+            score += 1.0;
+        } else if strength == opponent_strength {
+            score += 0.5;
+        }
+        total += 1.0;
+    });
+    score / total
+}
+
+/// strength of a hand over run-outs of the board to the river
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct HandFeatures {
+    ehs: f64,            // expected hand strength on the river
+    ehs2: f64,           // expected square of the hand strength, rewards draws
+    histogram: Vec<f64>, // fraction of run-outs with the hand strength in each equal bin
+}
+impl HandFeatures {
+    /// samples `num_run_out` run-outs of the board, there's only one on the river
+    pub fn new<R: Rng>(
+        hole: CardSet,
+        board: CardSet,
+        num_run_out: usize,
+        num_bin: usize,
+        rng: &mut R,
+    ) -> HandFeatures {
+        let num_run_out = if board.len() == 5 { 1 } else { num_run_out };
+        let mut features = HandFeatures {
+            ehs: 0.0,
+            ehs2: 0.0,
+            histogram: vec![0.0; num_bin],
+        };
+        for _ in 0..num_run_out {
+            let run_out = CardSet::draw_excluding(hole | board, 5 - board.len(), rng);
+            let strength = hand_strength(hole, board | run_out);
+            features.ehs += strength;
+            features.ehs2 += strength * strength;
+            let bin = ((strength * num_bin as f64) as usize).min(num_bin - 1);
+            features.histogram[bin] += 1.0;
+        }
+        features.ehs /= num_run_out as f64;
+        features.ehs2 /= num_run_out as f64;
+        for fraction in features.histogram.iter_mut() {
+            *fraction /= num_run_out as f64;
+        }
+        features
+    }
+    pub fn get_ehs(&self) -> f64 {
+        self.ehs
+    }
+    pub fn get_ehs2(&self) -> f64 {
+        self.ehs2
+    }
+    pub fn get_histogram(&self) -> &[f64] {
+        &self.histogram
+    }
+}
+
+/// what hands are clustered by
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Feature {
+    Ehs,
+    Ehs2,
+    Histogram, // potential aware, with the earth mover's distance
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug)]
+pub struct BucketCfg {
+    pub num_bucket: usize,
+    pub feature: Feature,
+    pub num_run_out: usize, // run-outs sampled for each hand
+    pub num_bin: usize,     // bins of the hand strength histograms
+    pub iterations: usize,  // of k-means
+    pub seed: u64,
+}
+impl Default for BucketCfg {
+    fn default() -> Self {
+        BucketCfg {
+            num_bucket: 8,
+            feature: Feature::Ehs2,
+            num_run_out: 100,
+            num_bin: 10,
+            iterations: 50,
+            seed: 0,
+        }
+    }
+}
+
+/// distance between two feature vectors
+pub type Distance = fn(&[f64], &[f64]) -> f64;
+
+/// squared euclidean distance
+pub fn euclidean_distance(a: &[f64], b: &[f64]) -> f64 {
+    a.iter().zip(b).map(|(x, y)| (x - y) * (x - y)).sum()
+}
+
+/// earth mover's distance between two histograms with the same total, in bins.
+/// in one dimension it's the area between the cumulative distributions
+pub fn earth_movers_distance(a: &[f64], b: &[f64]) -> f64 {
+    let mut carried = 0.0;
+    let mut distance = 0.0;
+    for (x, y) in a.iter().zip(b) {
+        carried += x - y;
+        distance += f64::abs(carried);
+    }
+    distance
+}
+
+/// Clusters the points into `k` clusters with k-means, starting from k-means++ centers.
+/// returns the cluster of each point
+pub fn kmeans<R: Rng>(
+    points: &[Vec<f64>],
+    k: usize,
+    distance: Distance,
+    iterations: usize,
+    rng: &mut R,
+) -> Vec<usize> {
+    assert!(k > 0 && k <= points.len(), "Can't make {} clusters", k);
+    let nearest = |point: &[f64], centers: &[Vec<f64>]| -> (usize, f64) {
+        centers
+            .iter()
+            .map(|center| distance(point, center))
+            .enumerate()
+            .min_by(|a, b| a.1.partial_cmp(&b.1).unwrap())
+            .unwrap()
+    };
+
+    // k-means++, next center is picked in proportion to the distance to the nearest center
+    let mut centers = vec![points[rng.gen_range(0..points.len())].clone()];
+    while centers.len() < k {
+        let distances: Vec<f64> = points
+            .iter()
+            .map(|point| nearest(point, &centers).1)
+            .collect();
+        let total: f64 = distances.iter().sum();
+        if total == 0.0 {
+            // fewer distinct points than clusters
+            centers.push(points[rng.gen_range(0..points.len())].clone());
+            continue;
+        }
+        let mut target = rng.gen_range(0.0..total);
+        let index = distances
+            .iter()
+            .position(|&d| {
+                target -= d;
+                target < 0.0
+            })
+            .unwrap_or(points.len() - 1);
+        centers.push(points[index].clone());
+    }
+
+    let mut clusters = vec![0; points.len()];
+    for _ in 0..iterations {
+        let new_clusters: Vec<usize> = points
+            .par_iter()
+            .map(|point| nearest(point, &centers).0)
+            .collect();
+        let changed = new_clusters != clusters;
+        clusters = new_clusters;
+        // move the centers to the mean of their points, empty clusters stay
+        let mut sums = vec![vec![0.0; points[0].len()]; k];
+        let mut counts = vec![0; k];
+        for (point, &cluster) in points.iter().zip(&clusters) {
+            for (sum, x) in sums[cluster].iter_mut().zip(point) {
+                *sum += x;
+            }
+            counts[cluster] += 1;
+        }
+        for ((center, sum), &count) in centers.iter_mut().zip(sums).zip(&counts) {
+            if count > 0 {
+                *center = sum.into_iter().map(|x| x / count as f64).collect();
+            }
+        }
+        if !changed {
+            break;
+        }
+    }
+    clusters
+}
+
+impl BettingRound {
+    /// indexer of the hole and the community cards dealt by this round
+    fn get_indexer(self) -> HandIndexer {
+        match self {
+            BettingRound::PreFlop => HandIndexer::preflop(),
+            BettingRound::Flop => HandIndexer::flop(),
+            BettingRound::Turn => HandIndexer::turn(),
+            BettingRound::River => HandIndexer::river(),
+        }
+    }
+}
+
+/// Bucket of every hand of a betting round, by the index of the hand up to suit isomorphism.
+/// Buckets are ordered by the average EHS of their hands, bucket 0 is the weakest.
+/// There're no tables for the river, its 2.4 billion hands don't fit in memory to be
+/// clustered, river hands are bucketed by hand strength instead, see `CardAbstraction`
+#[derive(Clone, Debug)]
+pub struct BucketTable {
+    betting_round: BettingRound,
+    num_bucket: usize,
+    buckets: Vec<u16>,
+    indexer: HandIndexer,
+}
+impl BucketTable {
+    /// Calculates the features of every hand in parallel, then clusters them.
+    /// takes long past the flop, use few run-outs to try it out.
+    /// returns error for the river
+    pub fn build(betting_round: BettingRound, cfg: BucketCfg) -> Result<BucketTable, String> {
+        if betting_round == BettingRound::River {
+            return Err("River hands are bucketed by hand strength, not a table".into());
+        }
+        let indexer = betting_round.get_indexer();
+        let features: Vec<HandFeatures> = (0..indexer.get_size())
+            .into_par_iter()
+            .map(|index| {
+                let cards = indexer.unindex(index);
+                let mut rng = StdRng::seed_from_u64(cfg.seed.wrapping_add(index));
+                HandFeatures::new(
+                    cards[..2].iter().collect(),
+                    cards[2..].iter().collect(),
+                    cfg.num_run_out,
+                    cfg.num_bin,
+                    &mut rng,
+                )
+            })
+            .collect();
+        let (points, distance): (Vec<Vec<f64>>, Distance) = match cfg.feature {
+            Feature::Ehs => (
+                features.iter().map(|f| vec![f.ehs]).collect(),
+                euclidean_distance,
+            ),
+            Feature::Ehs2 => (
+                features.iter().map(|f| vec![f.ehs2]).collect(),
+                euclidean_distance,
+            ),
+            Feature::Histogram => (
+                features.iter().map(|f| f.histogram.clone()).collect(),
+                earth_movers_distance,
+            ),
+        };
+        let mut rng = StdRng::seed_from_u64(cfg.seed);
+        let clusters = kmeans(&points, cfg.num_bucket, distance, cfg.iterations, &mut rng);
+
+        // relabel the clusters from the lowest average EHS
+        let mut ehs_sums = vec![(0.0, 0); cfg.num_bucket];
+        for (f, &cluster) in features.iter().zip(&clusters) {
+            ehs_sums[cluster].0 += f.ehs;
+            ehs_sums[cluster].1 += 1;
+        }
+        let mut order: Vec<usize> = (0..cfg.num_bucket).collect();
+        order.sort_by(|&a, &b| {
+            let average = |(sum, count): (f64, usize)| sum / count.max(1) as f64;
+            average(ehs_sums[a])
+                .partial_cmp(&average(ehs_sums[b]))
+                .unwrap()
+        });
+        let mut labels = vec![0; cfg.num_bucket];
+        for (label, &cluster) in order.iter().enumerate() {
+            labels[cluster] = label as u16;
+        }
+        Ok(BucketTable {
+            betting_round,
+            num_bucket: cfg.num_bucket,
+            buckets: clusters.iter().map(|&cluster| labels[cluster]).collect(),
+            indexer,
+        })
+    }
+    pub fn get_betting_round(&self) -> BettingRound {
+        self.betting_round
+    }
+    pub fn get_num_bucket(&self) -> usize {
+        self.num_bucket
+    }
+    /// bucket of a player's hole cards with the community cards of the table's betting round
+    pub fn get_bucket(&self, hole: &[Card], community: &[Card]) -> u16 {
+        self.buckets[self.indexer.index_hand(hole, community) as usize]
+    }
+
+    /// Writes the table as: "BKTS", version (u32), betting round (u8), number of buckets (u32),
+    /// number of hands (u64), then the bucket of each hand (u16). all little endian
+    pub fn save(&self, path: &Path) -> Result<(), String> {
+        let write = || -> std::io::Result<()> {
+            let mut writer = BufWriter::new(File::create(path)?);
+            writer.write_all(MAGIC)?;
+            writer.write_all(&TABLE_VERSION.to_le_bytes())?;
+            writer.write_all(&[self.betting_round as u8])?;
+            writer.write_all(&(self.num_bucket as u32).to_le_bytes())?;
+            writer.write_all(&(self.buckets.len() as u64).to_le_bytes())?;
+            for bucket in &self.buckets {
+                writer.write_all(&bucket.to_le_bytes())?;
+            }
+            writer.flush()
+        };
+        write().map_err(|e| format!("Can't write {:?}: {}", path, e))
+    }
+    pub fn load(path: &Path) -> Result<BucketTable, String> {
+        let mut reader =
+            BufReader::new(File::open(path).map_err(|e| format!("Can't read {:?}: {}", path, e))?);
+        let mut read = |num_byte: usize| -> Result<Vec<u8>, String> {
+            let mut bytes = vec![0; num_byte];
+            reader
+                .read_exact(&mut bytes)
+                .map_err(|e| format!("Can't read {:?}: {}", path, e))?;
+            Ok(bytes)
+        };
+        if read(4)? != MAGIC {
+            return Err(format!("{:?} isn't a bucket table", path));
+        }
+        let version = u32::from_le_bytes(read(4)?.try_into().unwrap());
+        if version != TABLE_VERSION {
+            return Err(format!(
+                "{:?} is version {}, expected {}",
+                path, version, TABLE_VERSION
+            ));
+        }
+        let betting_round = match read(1)?[0] {
+            0 => BettingRound::PreFlop,
+            1 => BettingRound::Flop,
+            2 => BettingRound::Turn,
+            3 => {
+                return Err(format!(
+                    "{:?} is for the river, which can't have a table",
+                    path
+                ))
+            }
+            round => return Err(format!("{:?} has an invalid betting round {}", path, round)),
+        };
+        let num_bucket = u32::from_le_bytes(read(4)?.try_into().unwrap()) as usize;
+        if num_bucket == 0 || num_bucket > u16::MAX as usize {
+            return Err(format!("{:?} has {} buckets", path, num_bucket));
+        }
+        let num_hand = u64::from_le_bytes(read(8)?.try_into().unwrap());
+        let indexer = betting_round.get_indexer();
+        if num_hand != indexer.get_size() {
+            return Err(format!(
+                "{:?} has {} hands, expected {}",
+                path,
+                num_hand,
+                indexer.get_size()
+            ));
+        }
+        let buckets: Vec<u16> = read(num_hand as usize * 2)?
+            .chunks_exact(2)
+            .map(|bytes| u16::from_le_bytes([bytes[0], bytes[1]]))
+            .collect();
+        // a bucket past the last would make keys of info sets that don't exist
+        if buckets.iter().any(|&bucket| bucket as usize >= num_bucket) {
+            return Err(format!("{:?} has a bucket out of {}", path, num_bucket));
+        }
+        Ok(BucketTable {
+            betting_round,
+            num_bucket,
+            buckets,
+            indexer,
+        })
+    }
+}
+
+/// how hands are bucketed in each betting round
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+#[serde(default)]
+pub struct CardAbstractionCfg {
+    // path of the bucket table of each betting round, see `BucketTable::save`.
+    // without one, hands are bucketed by their class before the flop and by equal
+    // ranges of hand strength after. the river always is, it can't have a table
+    pub tables: [Option<String>; 4],
+    pub num_strength_bucket: usize,
+}
+impl Default for CardAbstractionCfg {
+    fn default() -> Self {
+        CardAbstractionCfg {
+            tables: [None, None, None, None],
+            num_strength_bucket: 10,
+        }
+    }
+}
+
+/// buckets of the hands of every betting round
+#[derive(Clone, Debug)]
+pub struct CardAbstraction {
+    tables: Vec<Option<BucketTable>>,
+    num_strength_bucket: usize,
+}
+impl CardAbstraction {
+    /// loads the bucket tables, returns error if one is for another betting round
+    pub fn new(cfg: &CardAbstractionCfg) -> Result<CardAbstraction, String> {
+        let mut tables = Vec::new();
+        for (round, path) in cfg.tables.iter().enumerate() {
+            let table = match path {
+                Some(path) => Some(BucketTable::load(Path::new(path))?),
+                None => None,
+            };
+            if let Some(table) = &table {
+                if table.get_betting_round() as usize != round {
+                    return Err(format!(
+                        "{} is a bucket table for the {:?}",
+                        path.as_ref().unwrap(),
+                        table.get_betting_round()
+                    ));
+                }
+            }
+            tables.push(table);
+        }
+        if cfg.num_strength_bucket == 0 {
+            return Err("There has to be at least one hand strength bucket".into());
+        }
+        Ok(CardAbstraction {
+            tables,
+            num_strength_bucket: cfg.num_strength_bucket,
+        })
+    }
+    pub fn get_num_bucket(&self, betting_round: BettingRound) -> usize {
+        match &self.tables[betting_round as usize] {
+            Some(table) => table.get_num_bucket(),
+            None if betting_round == BettingRound::PreFlop => NUM_CLASSES,
+            None => self.num_strength_bucket,
+        }
+    }
+    /// hash of the bucket of every hand, to check the abstraction is the one a strategy
+    /// was trained with. it doesn't depend on where the tables are
+    pub fn get_hash(&self) -> u64 {
+        let tables = self.tables.iter().flat_map(|table| {
+            let (num_bucket, buckets) = match table {
+                Some(table) => (table.num_bucket, &table.buckets[..]),
+                None => (0, &[][..]),
+            };
+            [num_bucket as u64]
+                .into_iter()
+                .chain(buckets.iter().map(|&bucket| bucket as u64))
+        });
+        fnv_hash([self.num_strength_bucket as u64].into_iter().chain(tables))
+    }
+    /// bucket of the hole cards with the community cards dealt by `betting_round`
+    pub fn get_bucket(
+        &self,
+        betting_round: BettingRound,
+        hole: &[Card],
+        community: &[Card],
+    ) -> u16 {
+        let community = &community[..betting_round.get_num_community()];
+        match &self.tables[betting_round as usize] {
+            Some(table) => table.get_bucket(hole, community),
+            None if betting_round == BettingRound::PreFlop => {
+                HandClass::from_hole([hole[0], hole[1]]).get_index() as u16
+            }
+            None => {
+                let strength = hand_strength(hole.iter().collect(), community.iter().collect());
+                let bucket = (strength * self.num_strength_bucket as f64) as usize;
+                bucket.min(self.num_strength_bucket - 1) as u16
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn distances_and_kmeans() {
+        assert_eq!(
+            earth_movers_distance(&[1.0, 0.0, 0.0], &[0.0, 0.0, 1.0]),
+            2.0
+        );
+        assert_eq!(
+            earth_movers_distance(&[0.5, 0.5, 0.0], &[0.0, 0.5, 0.5]),
+            1.0
+        );
+
+        let points: Vec<Vec<f64>> = [0.0, 0.1, 0.2, 5.0, 5.1, 9.9, 10.0]
+            .iter()
+            .map(|&x| vec![x])
+            .collect();
+        let mut rng = StdRng::seed_from_u64(0);
+        let clusters = kmeans(&points, 3, euclidean_distance, 20, &mut rng);
+        assert!(clusters[0] == clusters[2] && clusters[3] == clusters[4]);
+        assert!(clusters[0] != clusters[3] && clusters[3] != clusters[6]);
+    }
+
+    #[test]
+    fn preflop_buckets() {
+        let cards = |s: &str| Card::parse_list(s).unwrap();
+        let cfg = BucketCfg {
+            num_bucket: 5,
+            feature: Feature::Ehs,
+            num_run_out: 3,
+            ..BucketCfg::default()
+        };
+        let table = BucketTable::build(BettingRound::PreFlop, cfg).unwrap();
+        let aces = table.get_bucket(&cards("As Ah"), &[]);
+        assert_eq!(aces, 4);
+        assert_eq!(aces, table.get_bucket(&cards("Ad Ac"), &[]));
+        assert!(table.get_bucket(&cards("7s 2h"), &[]) < aces);
+
+        let path = std::env::temp_dir().join("bucket_table_test.bin");
+        table.save(&path).unwrap();
+        let loaded = BucketTable::load(&path).unwrap();
+        assert_eq!(loaded.buckets, table.buckets);
+        assert_eq!(loaded.get_num_bucket(), 5);
+
+        // a corrupted number of buckets, the aces are in bucket 4
+        let bytes = std::fs::read(&path).unwrap();
+        for num_bucket in [4u32, 0, 1 << 16] {
+            let mut corrupted = bytes.clone();
+            corrupted[9..13].copy_from_slice(&num_bucket.to_le_bytes());
+            std::fs::write(&path, corrupted).unwrap();
+            assert!(BucketTable::load(&path).is_err());
+        }
+        std::fs::remove_file(path).unwrap();
+        assert!(BucketTable::build(BettingRound::River, cfg).is_err());
+    }
+}
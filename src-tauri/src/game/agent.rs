@@ -0,0 +1,321 @@
+//! Automated players. An agent sees the game from its seat and picks an action,
+//! a `Table` asks the agents of the bot seats to act until it's a person's turn
+
+use std::{ops::RangeInclusive, time::Duration};
+
+use serde::{Deserialize, Serialize};
+
+use super::card::Card;
+use super::{Action, ActionRecord, BettingRound, Game, Showdown, Variant};
+
+/// the game as seen from a seat, the other players' hole cards are hidden
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct SeatView {
+    seat_index: usize,
+    variant: Variant,
+    hole: Vec<Card>,
+    community: Vec<Card>,
+    betting_round: BettingRound,
+    discarding: bool, // has to discard a hole card instead of betting
+    dealer_index: usize,
+    big_blind_amount: usize,
+    pot_size: usize, // not including the bets of this round
+    stacks: Vec<usize>,
+    bet_sizes: Vec<usize>,
+    folded: Vec<bool>,
+    legal_actions: Vec<Action>, // bets and raises are Bet(0) and Raise(0), see `raise_or_bet_range`
+    call_amount: Option<usize>,
+    raise_or_bet_range: Option<RangeInclusive<usize>>,
+    history: Vec<ActionRecord>, // actions of the hand so far
+}
+impl SeatView {
+    /// view of the game from the seat of the current player
+    pub fn new(game: &Game) -> SeatView {
+        let seat_index = game.current_player_index;
+        SeatView {
+            seat_index,
+            variant: game.get_variant(),
+            hole: game.players[seat_index].hole.clone(),
+            community: game.community.clone(),
+            betting_round: game.betting_round,
+            discarding: game.discarding,
+            dealer_index: game.dealer_index,
+            big_blind_amount: game.get_big_blind_amount(),
+            pot_size: game.pot_size,
+            stacks: game.players.iter().map(|player| player.stack).collect(),
+            bet_sizes: game.players.iter().map(|player| player.bet_size).collect(),
+            folded: game.players.iter().map(|player| player.folded).collect(),
+            legal_actions: game.get_possible_actions(),
+            call_amount: game.get_call_amount(),
+            raise_or_bet_range: game.get_raise_or_bet_range(),
+            history: game.history.clone(),
+        }
+    }
+    pub fn get_seat_index(&self) -> usize {
+        self.seat_index
+    }
+    pub fn get_variant(&self) -> Variant {
+        self.variant
+    }
+    pub fn get_hole(&self) -> &[Card] {
+        &self.hole
+    }
+    pub fn get_community(&self) -> &[Card] {
+        &self.community
+    }
+    pub fn get_betting_round(&self) -> BettingRound {
+        self.betting_round
+    }
+    pub fn is_discarding(&self) -> bool {
+        self.discarding
+    }
+    pub fn get_dealer_index(&self) -> usize {
+        self.dealer_index
+    }
+    pub fn get_big_blind_amount(&self) -> usize {
+        self.big_blind_amount
+    }
+    pub fn get_pot_size(&self) -> usize {
+        self.pot_size
+    }
+    pub fn get_stacks(&self) -> &[usize] {
+        &self.stacks
+    }
+    pub fn get_bet_sizes(&self) -> &[usize] {
+        &self.bet_sizes
+    }
+    pub fn get_folded(&self) -> &[bool] {
+        &self.folded
+    }
+    pub fn get_legal_actions(&self) -> &[Action] {
+        &self.legal_actions
+    }
+    pub fn get_call_amount(&self) -> Option<usize> {
+        self.call_amount
+    }
+    pub fn get_raise_or_bet_range(&self) -> Option<RangeInclusive<usize>> {
+        self.raise_or_bet_range.clone()
+    }
+    pub fn get_history(&self) -> &[ActionRecord] {
+        &self.history
+    }
+}
+
+/// how a hand ended, sent to every agent
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct HandResult {
+    chip_changes: Vec<isize>,   // chips won or lost by each player over the hand
+    showdown: Option<Showdown>, // None if everyone else folded
+}
+impl HandResult {
+    pub fn get_chip_changes(&self) -> &[isize] {
+        &self.chip_changes
+    }
+    pub fn get_showdown(&self) -> Option<&Showdown> {
+        self.showdown.as_ref()
+    }
+}
+
+/// a program that plays a seat
+pub trait Agent {
+    /// picks one of the legal actions, with an amount in the range for bets and raises
+    fn act(&mut self, view: &SeatView) -> Action;
+    /// called after every hand, including the ones the agent folded
+    fn hand_finished(&mut self, _seat_index: usize, _result: &HandResult) {}
+}
+
+/// chips each player has in the hand, their stack and bet
+pub(super) fn get_chips(game: &Game) -> Vec<usize> {
+    game.players
+        .iter()
+        .map(|player| player.stack + player.bet_size)
+        .collect()
+}
+
+/// a game with an agent for each bot seat
+pub struct Table {
+    game: Game,
+    agents: Vec<Option<Box<dyn Agent + Send>>>, // None for seats played by a person
+    starting_chips: Vec<usize>,                 // chips of the players when the hand started
+}
+impl Table {
+    /// returns error if there isn't a seat for every player
+    pub fn new(game: Game, agents: Vec<Option<Box<dyn Agent + Send>>>) -> Result<Table, String> {
+        if agents.len() != Game::NUM_PLAYER {
+            return Err(format!(
+                "{} seats for {} players",
+                agents.len(),
+                Game::NUM_PLAYER
+            ));
+        }
+        let starting_chips = get_chips(&game);
+        Ok(Table {
+            game,
+            agents,
+            starting_chips,
+        })
+    }
+    /// seats the bots in the game's config, the other seats are played by people.
+    /// returns error if a bot can't be made
+    pub fn from_cfg(game: Game) -> Result<Table, String> {
+        let agents = game
+            .cfg
+            .bots
+            .iter()
+            .map(|bot| bot.as_ref().map(|bot| bot.new_agent(None)).transpose())
+            .collect::<Result<_, _>>()?;
+        Table::new(game, agents) // the config has a bot for every seat
+    }
+    pub fn get_game(&self) -> &Game {
+        &self.game
+    }
+    /// gives the agents back, to seat them somewhere else
+    pub fn into_agents(self) -> Vec<Option<Box<dyn Agent + Send>>> {
+        self.agents
+    }
+    /// replaces the game, for when it's played somewhere else like the frontend.
+    /// a new hand starts counting the chips from the new game
+    pub fn set_game(&mut self, game: Game) {
+        if game.hands_played != self.game.hands_played {
+            self.starting_chips = get_chips(&game);
+        }
+        self.game = game;
+    }
+    /// how long the bots wait before acting in the app
+    pub fn get_think_delay(&self) -> Duration {
+        Duration::from_millis(self.game.cfg.think_delay)
+    }
+    /// true if the current player is played by an agent and the session isn't over
+    pub fn is_bot_turn(&self) -> bool {
+        !self.game.session_over && self.agents[self.game.current_player_index].is_some()
+    }
+
+    /// applies the action of the current player, then tells the agents if the hand finished
+    pub fn act(&mut self, action: Action) -> Result<(), String> {
+        let new_game = self.game.act(action)?;
+        let finished = new_game.hands_played != self.game.hands_played;
+        self.game = new_game;
+        if finished {
+            let chips = get_chips(&self.game);
+            let result = HandResult {
+                // the blinds of the next hand are still counted in the chips
+                chip_changes: chips
+                    .iter()
+                    .zip(&self.starting_chips)
+                    .map(|(&after, &before)| after as isize - before as isize)
+                    .collect(),
+                showdown: self.game.last_showdown.clone(),
+            };
+            for (seat_index, agent) in self.agents.iter_mut().enumerate() {
+                if let Some(agent) = agent {
+                    agent.hand_finished(seat_index, &result);
+                }
+            }
+            self.starting_chips = chips;
+        }
+        Ok(())
+    }
+
+    /// lets the agent of the current seat act.
+    /// returns false without acting if it's a person's turn or the session is over
+    pub fn step(&mut self) -> Result<bool, String> {
+        if self.game.session_over {
+            return Ok(false);
+        }
+        let view = SeatView::new(&self.game);
+        let action = match &mut self.agents[self.game.current_player_index] {
+            Some(agent) => agent.act(&view),
+            None => return Ok(false),
+        };
+        self.act(action)?;
+        Ok(true)
+    }
+
+    /// lets the agents act until it's a person's turn.
+    /// returns error if an agent picks an illegal action
+    pub fn play_bots(&mut self) -> Result<(), String> {
+        while self.step()? {}
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::{Arc, Mutex};
+
+    /// checks or calls, and records the results it's told about
+    struct Passive {
+        results: Arc<Mutex<Vec<HandResult>>>,
+    }
+    impl Agent for Passive {
+        fn act(&mut self, view: &SeatView) -> Action {
+            if view.is_discarding() {
+                return Action::Discard(0);
+            }
+            *view
+                .get_legal_actions()
+                .iter()
+                .find(|action| matches!(action, Action::Check | Action::Call))
+                .unwrap()
+        }
+        fn hand_finished(&mut self, _seat_index: usize, result: &HandResult) {
+            self.results.lock().unwrap().push(result.clone());
+        }
+    }
+
+    fn new_game() -> Game {
+        Game::new(
+            serde_json::from_str(
+                r#"{
+                    "player_name": ["A", "B"],
+                    "starting_chip": [500, 500],
+                    "small_blind_amount": 10,
+                    "big_blind_amount": 20,
+                    "first_dealer_index": 0
+                }"#,
+            )
+            .unwrap(),
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn bots_play_hands() {
+        let results = Arc::new(Mutex::new(Vec::new()));
+        let passive = || -> Option<Box<dyn Agent + Send>> {
+            Some(Box::new(Passive {
+                results: results.clone(),
+            }))
+        };
+        let mut table = Table::new(new_game(), vec![passive(), None]).unwrap();
+        assert!(Table::new(new_game(), vec![passive()]).is_err());
+
+        // the dealer is the bot and acts first pre-flop, then it's the person's turn
+        assert!(table.is_bot_turn());
+        table.play_bots().unwrap();
+        assert!(!table.is_bot_turn());
+        assert_eq!(table.get_game().current_player_index, 1);
+        assert!(table.act(Action::Bet(0)).is_err());
+
+        // the person checks down, the bot is told about the showdown
+        while results.lock().unwrap().is_empty() {
+            if table.is_bot_turn() {
+                table.step().unwrap();
+            } else {
+                table.act(Action::Check).unwrap();
+            }
+        }
+        let result = results.lock().unwrap()[0].clone();
+        assert!(result.get_showdown().is_some());
+        assert_eq!(result.get_chip_changes().iter().sum::<isize>(), 0);
+        assert_eq!(table.get_game().hands_played, 1);
+
+        // the person is the small blind next hand and folds it without a showdown
+        assert!(!table.is_bot_turn());
+        table.act(Action::Fold).unwrap();
+        let result = results.lock().unwrap()[1].clone();
+        assert!(result.get_showdown().is_none());
+        assert_eq!(result.get_chip_changes(), [10, -10]);
+    }
+}
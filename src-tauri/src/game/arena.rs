@@ -0,0 +1,323 @@
+//! Headless matches between agents. Every hand starts from the config's stacks
+//! and is dealt from a seeded deck, so matches can be repeated
+
+use serde::{Deserialize, Serialize};
+
+use super::agent::{get_chips, Agent, Table};
+use super::bots::BotCfg;
+use super::card::Card;
+use super::equity::{self, EquityCfg, Holding};
+use super::{Game, GameCfg};
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct ArenaCfg {
+    pub game: GameCfg, // variant, stakes and the stacks of every hand
+    pub hands: usize,
+    // hand n is dealt from a deck shuffled with seed + n,
+    // the first bot is seeded with seed and the second with seed + 1
+    pub seed: u64,
+    pub duplicate: bool, // play every hand a second time with the seats swapped
+    // boards sampled for the equity of an all-in, when there're too many to enumerate
+    pub all_in_samples: usize,
+}
+
+/// mean of the samples with a 95% confidence interval, mean ± margin
+#[derive(Serialize, Deserialize, Clone, Copy, Debug)]
+pub struct Estimate {
+    mean: f64,
+    margin: f64,
+}
+impl Estimate {
+    pub fn get_mean(&self) -> f64 {
+        self.mean
+    }
+    pub fn get_margin(&self) -> f64 {
+        self.margin
+    }
+}
+
+/// running sums of samples, so millions of hands don't have to be kept
+#[derive(Default)]
+struct Samples {
+    count: usize,
+    sum: f64,
+    sum_squares: f64,
+}
+impl Samples {
+    fn add(&mut self, sample: f64) {
+        self.count += 1;
+        self.sum += sample;
+        self.sum_squares += sample * sample;
+    }
+    fn get_estimate(&self) -> Estimate {
+        let count = self.count.max(1) as f64;
+        let mean = self.sum / count;
+        // sample variance
+        let variance = (self.sum_squares - count * mean * mean).max(0.0) / (count - 1.0).max(1.0);
+        Estimate {
+            mean,
+            margin: 1.96 * (variance / count).sqrt(),
+        }
+    }
+}
+
+/// winnings of the first agent against the second, in milli big blinds per hand
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct MatchResult {
+    hands: usize, // hands played, twice `ArenaCfg::hands` in duplicate
+    winnings: Estimate,
+    // all-ins are counted as the share of the pot from the equity when the chips went in,
+    // instead of the chips won
+    all_in_adjusted: Estimate,
+}
+impl MatchResult {
+    pub fn get_hands(&self) -> usize {
+        self.hands
+    }
+    pub fn get_winnings(&self) -> Estimate {
+        self.winnings
+    }
+    pub fn get_all_in_adjusted(&self) -> Estimate {
+        self.all_in_adjusted
+    }
+}
+
+/// true if there's nothing left to bet, the players who haven't folded are all-in
+/// except one who has matched the bets
+fn is_all_in(game: &Game) -> bool {
+    let remaining: Vec<_> = game
+        .players
+        .iter()
+        .filter(|player| !player.folded)
+        .collect();
+    let max_bet = remaining
+        .iter()
+        .map(|player| player.bet_size)
+        .max()
+        .unwrap_or(0);
+    let with_chips: Vec<_> = remaining.iter().filter(|player| player.stack > 0).collect();
+    with_chips.len() < remaining.len()
+        && with_chips.len() <= 1
+        && with_chips.iter().all(|player| player.bet_size == max_bet)
+}
+
+/// chips each player is expected to end the hand with, from their equity when they went all-in
+/// with `community`. None if a player still has to discard
+fn get_all_in_chips(
+    game: &Game,
+    community: &[Card],
+    starting_chips: &[usize],
+    samples: usize,
+    seed: u64,
+) -> Result<Option<Vec<f64>>, String> {
+    let mut holdings = Vec::new();
+    let mut dead = Vec::new();
+    for player in game.players.iter() {
+        if player.folded {
+            dead.extend(player.hole.iter().copied());
+            continue;
+        }
+        match player.hole.clone().try_into() {
+            Ok(hole) => holdings.push(Holding::Hand(hole)),
+            Err(_) => return Ok(None),
+        }
+    }
+    let equity = equity::calculate(
+        &holdings,
+        community,
+        &dead,
+        EquityCfg {
+            samples,
+            seed: Some(seed),
+            ..EquityCfg::default()
+        },
+    )?;
+    // chips put in the pot, without the part nobody could call
+    let put_in: Vec<usize> = game
+        .players
+        .iter()
+        .zip(starting_chips)
+        .map(|(player, &starting)| starting - player.stack)
+        .collect();
+    let called: Vec<usize> = (0..put_in.len())
+        .map(|index| {
+            let most_by_others = (0..put_in.len())
+                .filter(|&other| other != index)
+                .map(|other| put_in[other])
+                .max()
+                .unwrap_or(0);
+            put_in[index].min(most_by_others)
+        })
+        .collect();
+    let pot: usize = called.iter().sum();
+    let mut shares = equity.get_equity().iter();
+    Ok(Some(
+        game.players
+            .iter()
+            .zip(starting_chips.iter().zip(called))
+            .map(|(player, (&starting, called))| {
+                let won = if player.folded {
+                    0.0
+                } else {
+                    shares.next().unwrap() / 100.0 * pot as f64
+                };
+                (starting - called) as f64 + won
+            })
+            .collect(),
+    ))
+}
+
+/// Plays a hand of a new game from `cfg`, then gives the agents back.
+/// returns the chips won by each seat in big blinds, as is and all-in adjusted
+fn play_hand(
+    cfg: &GameCfg,
+    agents: &mut Vec<Option<Box<dyn Agent + Send>>>,
+    all_in_samples: usize,
+) -> Result<(Vec<f64>, Vec<f64>), String> {
+    let game = Game::new(cfg.clone())?;
+    let big_blind_amount = game.get_big_blind_amount() as f64;
+    let starting_chips = get_chips(&game);
+    let mut table = Table::new(game, std::mem::take(agents))?;
+    let mut all_in_chips = None;
+    while table.get_game().hands_played == 0 {
+        let community = table.get_game().community.clone();
+        if !table.step()? {
+            return Err("Every seat has to be played by an agent".into());
+        }
+        let game = table.get_game();
+        if all_in_chips.is_none() && game.hands_played == 0 && is_all_in(game) {
+            all_in_chips = get_all_in_chips(
+                game,
+                &community,
+                &starting_chips,
+                all_in_samples,
+                cfg.seed.unwrap_or(0),
+            )?;
+        }
+    }
+    let chips = get_chips(table.get_game());
+    *agents = table.into_agents();
+    let to_big_blinds = |chips: Vec<f64>| -> Vec<f64> {
+        chips
+            .iter()
+            .zip(&starting_chips)
+            .map(|(&after, &before)| (after - before as f64) / big_blind_amount)
+            .collect()
+    };
+    let chips: Vec<f64> = chips.iter().map(|&chips| chips as f64).collect();
+    let all_in_chips = all_in_chips.unwrap_or_else(|| chips.clone());
+    Ok((to_big_blinds(chips), to_big_blinds(all_in_chips)))
+}
+
+/// Plays the first bot against the second for `cfg.hands` hands, alternating the button.
+/// In duplicate another pair of agents replays each hand with the seats swapped,
+/// so neither has seen the cards before
+pub fn play_match(cfg: &ArenaCfg, bots: [&BotCfg; 2]) -> Result<MatchResult, String> {
+    let new_agent = |index: usize| {
+        bots[index]
+            .new_agent(Some(cfg.seed.wrapping_add(index as u64)))
+            .map(Some)
+    };
+    let mut agents = vec![new_agent(0)?, new_agent(1)?];
+    // the second bot is in the first seat
+    let mut swapped_agents = if cfg.duplicate {
+        vec![new_agent(1)?, new_agent(0)?]
+    } else {
+        Vec::new()
+    };
+    let mut winnings = Samples::default();
+    let mut all_in_adjusted = Samples::default();
+    for hand in 0..cfg.hands {
+        let mut game_cfg = cfg.game.clone();
+        game_cfg.first_dealer_index = hand % Game::NUM_PLAYER;
+        game_cfg.seed = Some(cfg.seed.wrapping_add(hand as u64));
+        let (mut won, mut adjusted) = play_hand(&game_cfg, &mut agents, cfg.all_in_samples)?;
+        if cfg.duplicate {
+            let (swapped_won, swapped_adjusted) =
+                play_hand(&game_cfg, &mut swapped_agents, cfg.all_in_samples)?;
+            // the first agent is in the second seat
+            won[0] = (won[0] + swapped_won[1]) / 2.0;
+            adjusted[0] = (adjusted[0] + swapped_adjusted[1]) / 2.0;
+        }
+        winnings.add(won[0] * 1000.0);
+        all_in_adjusted.add(adjusted[0] * 1000.0);
+    }
+    Ok(MatchResult {
+        hands: if cfg.duplicate {
+            cfg.hands * 2
+        } else {
+            cfg.hands
+        },
+        winnings: winnings.get_estimate(),
+        all_in_adjusted: all_in_adjusted.get_estimate(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn new_cfg(duplicate: bool) -> ArenaCfg {
+        ArenaCfg {
+            game: serde_json::from_str(
+                r#"{
+                    "player_name": ["A", "B"],
+                    "starting_chip": [1000, 1000],
+                    "small_blind_amount": 5,
+                    "big_blind_amount": 10,
+                    "first_dealer_index": 0
+                }"#,
+            )
+            .unwrap(),
+            hands: 200,
+            seed: 0,
+            duplicate,
+            all_in_samples: 1000,
+        }
+    }
+
+    #[test]
+    fn matches() {
+        let play = |cfg: &ArenaCfg, first: BotCfg, second: BotCfg| {
+            play_match(cfg, [&first, &second]).unwrap()
+        };
+        // seeded matches repeat
+        let cfg = new_cfg(false);
+        let result = play(&cfg, BotCfg::Random, BotCfg::CallingStation);
+        let again = play(&cfg, BotCfg::Random, BotCfg::CallingStation);
+        assert_eq!(result.get_hands(), 200);
+        assert_eq!(
+            result.get_winnings().get_mean(),
+            again.get_winnings().get_mean()
+        );
+        assert!(result.get_winnings().get_margin() > 0.0);
+
+        // a bot breaks even against itself in duplicate, it gets the same cards in both seats
+        let cfg = new_cfg(true);
+        let result = play(&cfg, BotCfg::CallingStation, BotCfg::CallingStation);
+        assert_eq!(result.get_hands(), 400);
+        assert_eq!(result.get_winnings().get_mean(), 0.0);
+        assert_eq!(result.get_all_in_adjusted().get_mean(), 0.0);
+    }
+
+    #[test]
+    fn all_in_equity() {
+        let mut game = Game::new(new_cfg(false).game).unwrap();
+        let cards = |s: &str| Card::parse_list(s).unwrap();
+        game.players[0].hole = cards("As Ah");
+        game.players[1].hole = cards("Kd Kc");
+        // aces shove and kings call before the flop
+        let starting_chips = get_chips(&game);
+        game = game.act(super::super::Action::Raise(990)).unwrap();
+        assert!(!is_all_in(&game));
+        let community = game.community.clone();
+        game = game.act(super::super::Action::Call).unwrap();
+        assert!(is_all_in(&game));
+        let chips = get_all_in_chips(&game, &community, &starting_chips, 10_000, 0)
+            .unwrap()
+            .unwrap();
+        // aces are about 82% against kings
+        assert!((chips[0] - 0.82 * 2000.0).abs() < 20.0);
+        assert!((chips[0] + chips[1] - 2000.0).abs() < 1e-6);
+    }
+}
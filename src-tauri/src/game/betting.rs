@@ -0,0 +1,384 @@
+//! Betting abstraction of heads-up no-limit hold'em. A solver only tries bets of a few
+//! fractions of the pot and all-in, `BettingState` plays the abstract actions out in chips.
+//! Bets of other sizes are translated to the abstract bets around them
+
+use std::cmp::{max, min};
+
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+
+use super::BettingRound;
+
+/// bet sizes the solver tries
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(default)]
+pub struct BettingAbstraction {
+    pub bet_sizes: [Vec<f64>; 4], // pot fractions of the first bet of each betting round
+    pub raise_sizes: [Vec<f64>; 4], // pot fractions of the raises after it
+    pub all_in: bool,             // moving all-in is always an action
+    pub max_raises: usize,        // bets and raises a round before only all-in is left
+}
+impl Default for BettingAbstraction {
+    fn default() -> Self {
+        BettingAbstraction {
+            bet_sizes: [vec![1.0], vec![0.5, 1.0], vec![0.5, 1.0], vec![0.5, 1.0]],
+            raise_sizes: [vec![1.0], vec![1.0], vec![1.0], vec![1.0]],
+            all_in: true,
+            max_raises: 3,
+        }
+    }
+}
+
+/// an action of the abstraction, bets are raises if there's a bet to call
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum AbstractAction {
+    Fold,
+    Call,    // checks if there's no bet
+    Bet(u8), // index of the pot fraction in the bet or raise sizes of the round
+    AllIn,
+}
+impl AbstractAction {
+    /// byte of the action in the history
+    fn to_byte(self) -> u8 {
+        match self {
+            AbstractAction::Fold => 0,
+            AbstractAction::Call => 1,
+            AbstractAction::AllIn => 2,
+            AbstractAction::Bet(index) => 3 + index,
+        }
+    }
+}
+
+/// how a bet that isn't in the abstraction is mapped to one that is
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum ActionTranslation {
+    Nearest, // the abstract bet nearest in fraction of the pot, easy to exploit
+    #[default]
+    PseudoHarmonic, // one of the abstract bets around it at random, see `pseudo_harmonic`
+}
+
+/// Probability of mapping a bet of `x` times the pot to the smaller abstract bet `a` instead
+/// of the larger `b`, by the pseudo-harmonic mapping of Ganzfried and Sandholm.
+/// 1 at `a`, 0 at `b`, and exploiting it by betting in between doesn't win much
+pub fn pseudo_harmonic(a: f64, b: f64, x: f64) -> f64 {
+    (b - x) * (1.0 + a) / ((b - a) * (1.0 + x))
+}
+
+/// Betting of a heads-up hand in chips. Player 0 is the dealer, who posts the small blind
+/// and acts first before the flop
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct BettingState {
+    stack: usize, // chips of each player at the start of the hand
+    big_blind_amount: usize,
+    betting_round: BettingRound,
+    stacks: [usize; 2],
+    bets: [usize; 2],  // of the current round
+    pot: usize,        // bets of the previous rounds
+    player: usize,     // to act
+    min_raise: usize,  // like `Game`, the last bet or raise and the big blind at first
+    raises: usize,     // bets and raises of the current round
+    num_action: usize, // of the current round
+    over: bool,        // someone folded or it's the showdown
+    history: Vec<u8>,  // every action of the hand, see `AbstractAction::to_byte`
+}
+impl BettingState {
+    /// start of a hand with the blinds posted, returns error if a stack can't pay them
+    pub fn new(
+        stack: usize,
+        small_blind_amount: usize,
+        big_blind_amount: usize,
+    ) -> Result<BettingState, String> {
+        if stack <= big_blind_amount {
+            return Err(format!(
+                "Stack of {} can't pay the big blind of {}",
+                stack, big_blind_amount
+            ));
+        }
+        Ok(BettingState {
+            stack,
+            big_blind_amount,
+            betting_round: BettingRound::PreFlop,
+            stacks: [stack - small_blind_amount, stack - big_blind_amount],
+            bets: [small_blind_amount, big_blind_amount],
+            pot: 0,
+            player: 0,
+            min_raise: big_blind_amount,
+            raises: 0,
+            num_action: 0,
+            over: false,
+            history: Vec::new(),
+        })
+    }
+    pub fn get_betting_round(&self) -> BettingRound {
+        self.betting_round
+    }
+    /// player to act, None when the hand is over
+    pub fn get_player(&self) -> Option<usize> {
+        if self.over {
+            None
+        } else {
+            Some(self.player)
+        }
+    }
+    pub fn is_over(&self) -> bool {
+        self.over
+    }
+    /// bytes of the actions so far, the same for every state with the same actions
+    pub fn get_history(&self) -> &[u8] {
+        &self.history
+    }
+    /// chips in the pot including the bets of the current round
+    pub fn get_pot(&self) -> usize {
+        self.pot + self.bets[0] + self.bets[1]
+    }
+    pub fn get_to_call(&self) -> usize {
+        self.bets[1 - self.player].saturating_sub(self.bets[self.player])
+    }
+    /// chips a player has put in the pot this hand
+    pub fn get_put_in(&self, player: usize) -> usize {
+        self.stack - self.stacks[player]
+    }
+    /// player who folded, None if nobody did
+    pub fn get_folded(&self) -> Option<usize> {
+        match self.history.last() {
+            Some(&byte) if self.over && byte == AbstractAction::Fold.to_byte() => Some(self.player),
+            _ => None,
+        }
+    }
+
+    /// pot fractions the player to act can bet, the opening raise before the flop is a bet
+    pub fn get_bet_sizes<'a>(&self, abstraction: &'a BettingAbstraction) -> &'a [f64] {
+        let round = self.betting_round as usize;
+        if self.raises == 0 {
+            &abstraction.bet_sizes[round]
+        } else {
+            &abstraction.raise_sizes[round]
+        }
+    }
+
+    /// chips a bet or all-in raises by, over the bet to call
+    pub fn get_amount(&self, abstraction: &BettingAbstraction, action: AbstractAction) -> usize {
+        let max_amount = self.stacks[self.player].saturating_sub(self.get_to_call());
+        match action {
+            AbstractAction::Bet(index) => {
+                let fraction = self.get_bet_sizes(abstraction)[index as usize];
+                let pot = (self.get_pot() + self.get_to_call()) as f64;
+                let amount = max((fraction * pot).round() as usize, self.min_raise);
+                min(amount, max_amount)
+            }
+            AbstractAction::AllIn => max_amount,
+            _ => 0,
+        }
+    }
+
+    /// fraction of the pot after calling that a bet or all-in raises by
+    pub fn get_fraction(&self, abstraction: &BettingAbstraction, action: AbstractAction) -> f64 {
+        let pot = self.get_pot() + self.get_to_call();
+        self.get_amount(abstraction, action) as f64 / pot as f64
+    }
+
+    /// Maps a bet or raise by `fraction` of the pot after calling to a bet or all-in of the
+    /// abstraction. bets smaller or larger than every abstract bet go to the nearest one.
+    /// calls if the abstraction can't bet
+    pub fn translate<R: Rng>(
+        &self,
+        abstraction: &BettingAbstraction,
+        translation: ActionTranslation,
+        fraction: f64,
+        rng: &mut R,
+    ) -> AbstractAction {
+        let mut bets: Vec<(f64, AbstractAction)> = self
+            .get_actions(abstraction)
+            .into_iter()
+            .filter(|action| matches!(action, AbstractAction::Bet(_) | AbstractAction::AllIn))
+            .map(|action| (self.get_fraction(abstraction, action), action))
+            .collect();
+        bets.sort_by(|a, b| a.0.partial_cmp(&b.0).unwrap());
+        let (smaller, larger) = match bets.iter().position(|&(bet, _)| bet >= fraction) {
+            Some(0) => return bets[0].1,
+            Some(index) => (bets[index - 1], bets[index]),
+            None => {
+                return bets
+                    .last()
+                    .map_or(AbstractAction::Call, |&(_, action)| action)
+            }
+        };
+        let probability = match translation {
+            ActionTranslation::Nearest => {
+                if fraction - smaller.0 < larger.0 - fraction {
+                    1.0
+                } else {
+                    0.0
+                }
+            }
+            ActionTranslation::PseudoHarmonic => pseudo_harmonic(smaller.0, larger.0, fraction),
+        };
+        if rng.gen::<f64>() < probability {
+            smaller.1
+        } else {
+            larger.1
+        }
+    }
+
+    /// actions of the player to act in the abstraction, bets that come to the same amount
+    /// are only tried once
+    pub fn get_actions(&self, abstraction: &BettingAbstraction) -> Vec<AbstractAction> {
+        if self.over {
+            return Vec::new();
+        }
+        let mut actions = Vec::new();
+        // folding without a bet to call is never better than checking
+        if self.get_to_call() > 0 {
+            actions.push(AbstractAction::Fold);
+        }
+        actions.push(AbstractAction::Call);
+        let max_amount = self.stacks[self.player].saturating_sub(self.get_to_call());
+        // nothing to raise if either player can't put in more chips
+        if max_amount == 0 || self.stacks[1 - self.player] == 0 {
+            return actions;
+        }
+        let mut amounts = Vec::new();
+        if self.raises < abstraction.max_raises {
+            for index in 0..self.get_bet_sizes(abstraction).len() as u8 {
+                let amount = self.get_amount(abstraction, AbstractAction::Bet(index));
+                let all_in = abstraction.all_in && amount == max_amount;
+                if !all_in && !amounts.contains(&amount) {
+                    amounts.push(amount);
+                    actions.push(AbstractAction::Bet(index));
+                }
+            }
+        }
+        if abstraction.all_in {
+            actions.push(AbstractAction::AllIn);
+        }
+        actions
+    }
+
+    pub fn apply(&self, abstraction: &BettingAbstraction, action: AbstractAction) -> BettingState {
+        let mut next = self.clone();
+        let player = self.player;
+        let to_call = self.get_to_call();
+        next.history.push(action.to_byte());
+        next.num_action += 1;
+        match action {
+            AbstractAction::Fold => {
+                next.over = true;
+                return next;
+            }
+            AbstractAction::Call => {
+                let amount = min(to_call, self.stacks[player]);
+                next.stacks[player] -= amount;
+                next.bets[player] += amount;
+                // checking or calling ends the round unless it's the first action,
+                // the big blind still acts after the small blind calls
+                if next.num_action >= 2 {
+                    next.next_betting_round();
+                    return next;
+                }
+            }
+            AbstractAction::Bet(_) | AbstractAction::AllIn => {
+                let amount = self.get_amount(abstraction, action);
+                next.stacks[player] -= to_call + amount;
+                next.bets[player] += to_call + amount;
+                next.min_raise = amount;
+                next.raises += 1;
+            }
+        }
+        next.player = 1 - player;
+        next
+    }
+
+    /// collects the bets, the hand is over after the river or when someone is all-in
+    fn next_betting_round(&mut self) {
+        // return the part of a bet that couldn't be called
+        let called = min(self.bets[0], self.bets[1]);
+        for player in 0..2 {
+            self.stacks[player] += self.bets[player] - called;
+        }
+        self.pot += 2 * called;
+        self.bets = [0, 0];
+        if self.betting_round == BettingRound::River || self.stacks.contains(&0) {
+            self.over = true;
+            return;
+        }
+        self.betting_round.next();
+        // the big blind acts first after the flop
+        self.player = 1;
+        self.min_raise = self.big_blind_amount;
+        self.raises = 0;
+        self.num_action = 0;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::{rngs::StdRng, SeedableRng};
+
+    #[test]
+    fn abstract_betting() {
+        let abstraction = BettingAbstraction::default();
+        let state = BettingState::new(200, 1, 2).unwrap();
+        assert!(BettingState::new(2, 1, 2).is_err());
+        // the small blind can raise by the pot to 6 or move all-in
+        use AbstractAction::*;
+        assert_eq!(state.get_actions(&abstraction), [Fold, Call, Bet(0), AllIn]);
+        assert_eq!(state.get_amount(&abstraction, Bet(0)), 4);
+
+        // the big blind checks after a call and the flop starts with them
+        let state = state.apply(&abstraction, Call);
+        assert_eq!(state.get_player(), Some(1));
+        assert_eq!(state.get_actions(&abstraction), [Call, Bet(0), AllIn]);
+        let state = state.apply(&abstraction, Call);
+        assert_eq!(state.get_betting_round(), BettingRound::Flop);
+        assert_eq!((state.get_pot(), state.get_player()), (4, Some(1)));
+        assert_eq!(
+            state.get_actions(&abstraction),
+            [Call, Bet(0), Bet(1), AllIn]
+        );
+
+        // half pot is raised to the minimum, raises are by the pot after calling
+        let state = state.apply(&abstraction, Bet(0));
+        assert_eq!((state.get_put_in(1), state.get_to_call()), (4, 2));
+        assert_eq!(state.get_actions(&abstraction), [Fold, Call, Bet(0), AllIn]);
+        assert_eq!(state.get_amount(&abstraction, Bet(0)), 8);
+        let state = state.apply(&abstraction, Bet(0));
+        assert_eq!(state.get_put_in(0), 12);
+
+        // the raises run out, then all-in is called and the hand is over
+        let state = state.apply(&abstraction, Bet(0));
+        assert_eq!(state.get_actions(&abstraction), [Fold, Call, AllIn]);
+        let state = state.apply(&abstraction, AllIn);
+        assert_eq!(state.get_actions(&abstraction), [Fold, Call]);
+        assert_eq!(state.get_folded(), None);
+        let state = state.apply(&abstraction, Call);
+        assert!(state.is_over() && state.get_player().is_none());
+        assert_eq!((state.get_pot(), state.get_folded()), (400, None));
+        assert_eq!(state.get_history(), [1, 1, 3, 3, 3, 2, 1]);
+    }
+
+    #[test]
+    fn action_translation() {
+        // half the way to the larger bet maps to the smaller one less than half the time
+        assert_eq!(pseudo_harmonic(0.5, 1.0, 0.5), 1.0);
+        assert_eq!(pseudo_harmonic(0.5, 1.0, 1.0), 0.0);
+        assert!((pseudo_harmonic(0.5, 1.0, 0.75) - 3.0 / 7.0).abs() < 1e-9);
+
+        use AbstractAction::*;
+        let abstraction = BettingAbstraction::default();
+        let mut rng = StdRng::seed_from_u64(0);
+        let state = BettingState::new(200, 1, 2).unwrap();
+        let state = state.apply(&abstraction, Call).apply(&abstraction, Call);
+        let mut translate =
+            |translation, fraction| state.translate(&abstraction, translation, fraction, &mut rng);
+        // outside the abstract bets, and between them
+        assert_eq!(translate(ActionTranslation::PseudoHarmonic, 0.1), Bet(0));
+        assert_eq!(translate(ActionTranslation::PseudoHarmonic, 100.0), AllIn);
+        assert_eq!(translate(ActionTranslation::Nearest, 0.7), Bet(0));
+        assert_eq!(translate(ActionTranslation::Nearest, 0.8), Bet(1));
+        let smaller = (0..10_000)
+            .filter(|_| translate(ActionTranslation::PseudoHarmonic, 0.75) == Bet(0))
+            .count();
+        assert!((4_100..4_500).contains(&smaller));
+    }
+}
@@ -0,0 +1,260 @@
+//! Blueprint strategy, the average strategy of Monte Carlo CFR on abstracted hold'em
+//! written to a file for the blueprint bot to play. The file is memory mapped, so only the
+//! pages of the info sets the bot looks up are read, and bots that load the same file
+//! read them from the same page cache
+
+use std::{
+    cmp::Ordering,
+    fs::{self, File},
+    path::Path,
+};
+
+use memmap2::{Mmap, MmapMut};
+
+use super::holdem_tree::{get_betting_hash, HoldemTree, HoldemTreeCfg};
+use super::mccfr::Mccfr;
+use super::Variant;
+
+/// start of a blueprint file
+const MAGIC: &[u8; 4] = b"BLPT";
+/// version of the blueprint file, bumped when the format changes
+const BLUEPRINT_VERSION: u32 = 2;
+
+/// probabilities of the actions of every info set reached in training
+pub struct Blueprint {
+    variant: Variant,
+    cfg: HoldemTreeCfg, // abstraction it was trained on
+    betting_hash: u64,
+    card_hash: u64,
+    iterations: usize,
+    num_info_set: usize,
+    index: usize, // where the offsets of the info sets start
+    bytes: Mmap,  // the whole file
+}
+impl Blueprint {
+    /// returns error if the key of an info set is too long for the file, see `save`
+    pub fn new(mccfr: &Mccfr<HoldemTree>) -> Result<Blueprint, String> {
+        let tree = mccfr.get_game();
+        let mut strategies = mccfr.get_average_strategies();
+        strategies.sort_unstable_by(|(a, _), (b, _)| a.cmp(b));
+        let cfg = serde_json::to_vec(tree.get_cfg()).unwrap();
+        let variant = serde_json::to_vec(&Variant::NoLimitHoldem).unwrap();
+
+        let mut bytes = Vec::new();
+        bytes.extend_from_slice(MAGIC);
+        bytes.extend_from_slice(&BLUEPRINT_VERSION.to_le_bytes());
+        bytes.extend_from_slice(&(variant.len() as u32).to_le_bytes());
+        bytes.extend_from_slice(&variant);
+        bytes.extend_from_slice(&get_betting_hash(tree.get_cfg()).to_le_bytes());
+        bytes.extend_from_slice(&tree.get_card_abstraction().get_hash().to_le_bytes());
+        bytes.extend_from_slice(&(mccfr.get_iterations() as u64).to_le_bytes());
+        bytes.extend_from_slice(&(cfg.len() as u32).to_le_bytes());
+        bytes.extend_from_slice(&cfg);
+        write_info_sets(&mut bytes, &strategies)?;
+
+        let mut map = MmapMut::map_anon(bytes.len()).unwrap();
+        map.copy_from_slice(&bytes);
+        Ok(Blueprint::parse(map.make_read_only().unwrap()).unwrap())
+    }
+    pub fn get_variant(&self) -> Variant {
+        self.variant
+    }
+    pub fn get_cfg(&self) -> &HoldemTreeCfg {
+        &self.cfg
+    }
+    /// hash of the stacks, the blinds and the betting abstraction
+    pub fn get_betting_hash(&self) -> u64 {
+        self.betting_hash
+    }
+    /// hash of the buckets of the card abstraction, see `CardAbstraction::get_hash`
+    pub fn get_card_hash(&self) -> u64 {
+        self.card_hash
+    }
+    pub fn get_iterations(&self) -> usize {
+        self.iterations
+    }
+    pub fn get_num_info_sets(&self) -> usize {
+        self.num_info_set
+    }
+
+    /// key and quantized probabilities of the info set at `index` in the order of the keys,
+    /// None if the file is corrupted
+    fn get_entry(&self, index: usize) -> Option<(&[u8], &[u8])> {
+        let offset = |index: usize| -> Option<usize> {
+            let start = self.index + 8 * index;
+            let bytes = self.bytes.get(start..start + 8)?;
+            Some(u64::from_le_bytes(bytes.try_into().unwrap()) as usize)
+        };
+        let entries = self.index + 8 * (self.num_info_set + 1);
+        let entry = self
+            .bytes
+            .get(entries + offset(index)?..entries + offset(index + 1)?)?;
+        let (&key_len, entry) = entry.split_first()?;
+        (entry.len() >= key_len as usize).then(|| entry.split_at(key_len as usize))
+    }
+    /// probabilities of the actions at an info set of `HoldemTree`, None if it wasn't reached.
+    /// a binary search over the keys, it only reads a few pages of the file
+    pub fn get_strategy(&self, info_set: &[u8]) -> Option<Vec<f32>> {
+        let (mut low, mut high) = (0, self.num_info_set);
+        while low < high {
+            let middle = (low + high) / 2;
+            let (key, probabilities) = self.get_entry(middle)?;
+            match key.cmp(info_set) {
+                Ordering::Less => low = middle + 1,
+                Ordering::Greater => high = middle,
+                Ordering::Equal => {
+                    // rounding leaves them a little off a total of 1
+                    let total: f32 = probabilities.iter().map(|&p| p as f32).sum();
+                    return Some(probabilities.iter().map(|&p| p as f32 / total).collect());
+                }
+            }
+        }
+        None
+    }
+
+    /// Writes the blueprint as: "BLPT", version (u32), length of the variant (u32), the
+    /// variant in JSON, hash of the betting abstraction (u64), hash of the card abstraction
+    /// (u64), iterations (u64), length of the config (u32), the config in JSON, number of
+    /// info sets (u64), the offset of each info set from the end of the offsets then the end
+    /// of the last (u64), then for each info set in the order of their keys the length of its
+    /// key (u8), the key and the probability of each action in 255ths (u8). all little endian.
+    /// it's written next to `path` then renamed, a bot may have the old file mapped
+    pub fn save(&self, path: &Path) -> Result<(), String> {
+        let temporary = path.with_extension("tmp");
+        fs::write(&temporary, &self.bytes[..])
+            .and_then(|_| fs::rename(&temporary, path))
+            .map_err(|e| format!("Can't write {:?}: {}", path, e))
+    }
+    /// maps the file, the info sets are read when they're looked up
+    pub fn load(path: &Path) -> Result<Blueprint, String> {
+        let file = File::open(path).map_err(|e| format!("Can't read {:?}: {}", path, e))?;
+        // the file mustn't change while it's mapped, `save` replaces it instead of writing it
+        let bytes =
+            unsafe { Mmap::map(&file) }.map_err(|e| format!("Can't read {:?}: {}", path, e))?;
+        Blueprint::parse(bytes).map_err(|e| format!("{:?} {}", path, e))
+    }
+
+    /// reads the header of a blueprint file
+    fn parse(bytes: Mmap) -> Result<Blueprint, String> {
+        let mut position = 0;
+        let mut read = |num_byte: usize| -> Result<&[u8], String> {
+            let read = bytes
+                .get(position..)
+                .and_then(|rest| rest.get(..num_byte))
+                .ok_or("is too short")?;
+            position += num_byte;
+            Ok(read)
+        };
+        if read(4)? != MAGIC {
+            return Err("isn't a blueprint".into());
+        }
+        let version = u32::from_le_bytes(read(4)?.try_into().unwrap());
+        if version != BLUEPRINT_VERSION {
+            return Err(format!(
+                "is version {}, expected {}",
+                version, BLUEPRINT_VERSION
+            ));
+        }
+        let variant_len = u32::from_le_bytes(read(4)?.try_into().unwrap()) as usize;
+        let variant = serde_json::from_slice(read(variant_len)?)
+            .map_err(|e| format!("has an unknown variant: {}", e))?;
+        let betting_hash = u64::from_le_bytes(read(8)?.try_into().unwrap());
+        let card_hash = u64::from_le_bytes(read(8)?.try_into().unwrap());
+        let iterations = u64::from_le_bytes(read(8)?.try_into().unwrap()) as usize;
+        let cfg_len = u32::from_le_bytes(read(4)?.try_into().unwrap()) as usize;
+        let cfg: HoldemTreeCfg = serde_json::from_slice(read(cfg_len)?)
+            .map_err(|e| format!("has a config that can't be parsed: {}", e))?;
+        if get_betting_hash(&cfg) != betting_hash {
+            return Err("has a config that doesn't match its hash".into());
+        }
+        let num_info_set = u64::from_le_bytes(read(8)?.try_into().unwrap()) as usize;
+        if num_info_set >= bytes.len() / 8 {
+            return Err("is too short".into());
+        }
+        // the offsets and the entries are read on lookup, only check they fit
+        let offsets = read(8 * (num_info_set + 1))?;
+        let end = u64::from_le_bytes(offsets[offsets.len() - 8..].try_into().unwrap()) as usize;
+        read(end)?;
+        let index = position - end - offsets.len();
+        Ok(Blueprint {
+            variant,
+            cfg,
+            betting_hash,
+            card_hash,
+            iterations,
+            num_info_set,
+            index,
+            bytes,
+        })
+    }
+}
+
+/// Writes the number of info sets, their offsets and the info sets sorted by key.
+/// returns error if a key is longer than 255 bytes, its length is written in a byte
+fn write_info_sets(bytes: &mut Vec<u8>, strategies: &[(Vec<u8>, Vec<f64>)]) -> Result<(), String> {
+    // keys are a bucket and a betting history, only a long history of raises gets near
+    if let Some((info_set, _)) = strategies.iter().find(|(info_set, _)| info_set.len() > 255) {
+        return Err(format!(
+            "Info set key of {} bytes is too long, the most is 255. allow fewer raises",
+            info_set.len()
+        ));
+    }
+    bytes.extend_from_slice(&(strategies.len() as u64).to_le_bytes());
+    let mut offset = 0;
+    for (info_set, strategy) in strategies.iter() {
+        bytes.extend_from_slice(&(offset as u64).to_le_bytes());
+        offset += 1 + info_set.len() + strategy.len();
+    }
+    bytes.extend_from_slice(&(offset as u64).to_le_bytes());
+    for (info_set, strategy) in strategies.iter() {
+        bytes.push(info_set.len() as u8);
+        bytes.extend_from_slice(info_set);
+        bytes.extend(strategy.iter().map(|&p| (p * 255.0).round() as u8));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::mccfr::MccfrCfg;
+    use super::*;
+
+    #[test]
+    fn save_and_load() {
+        let tree = HoldemTree::new(HoldemTreeCfg::default()).unwrap();
+        let card_hash = tree.get_card_abstraction().get_hash();
+        let mut mccfr = Mccfr::new(tree, MccfrCfg::default()).unwrap();
+        mccfr.iterate(50);
+        let blueprint = Blueprint::new(&mccfr).unwrap();
+        let path = std::env::temp_dir().join("blueprint_test.bin");
+        blueprint.save(&path).unwrap();
+        let loaded = Blueprint::load(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(loaded.get_variant(), Variant::NoLimitHoldem);
+        assert_eq!(loaded.get_cfg(), blueprint.get_cfg());
+        assert_eq!(loaded.get_card_hash(), card_hash);
+        assert_eq!(loaded.get_iterations(), 50);
+        assert_eq!(loaded.get_num_info_sets(), mccfr.get_num_info_sets());
+        // probabilities are kept to about 1/255
+        for (info_set, strategy) in mccfr.get_average_strategies() {
+            let loaded = loaded.get_strategy(&info_set).unwrap();
+            assert_eq!(loaded.len(), strategy.len());
+            for (p, q) in strategy.iter().zip(loaded) {
+                assert!((*p as f32 - q).abs() < 0.01);
+            }
+        }
+        assert_eq!(loaded.get_strategy(&[255, 255]), None);
+        assert!(Blueprint::load(&path).is_err());
+
+        // another abstraction gives another hash
+        let mut cfg = HoldemTreeCfg::default();
+        assert_eq!(get_betting_hash(&cfg), loaded.get_betting_hash());
+        cfg.betting.max_raises += 1;
+        assert_ne!(get_betting_hash(&cfg), loaded.get_betting_hash());
+
+        // key lengths are written in a byte
+        let mut bytes = Vec::new();
+        assert!(write_info_sets(&mut bytes, &[(vec![0; 255], vec![1.0])]).is_ok());
+        assert!(write_info_sets(&mut bytes, &[(vec![0; 256], vec![1.0])]).is_err());
+    }
+}
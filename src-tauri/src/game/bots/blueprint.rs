@@ -0,0 +1,259 @@
+use std::path::Path;
+
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use serde::{Deserialize, Serialize};
+
+use super::super::abstraction::CardAbstraction;
+use super::super::betting::{AbstractAction, ActionTranslation, BettingState};
+use super::super::blueprint::Blueprint;
+use super::super::holdem_tree::HoldemTree;
+use super::super::ActionRecord;
+use super::{check_or_call, keep_best_hole, Action, Agent, HandResult, SeatView};
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct BlueprintCfg {
+    pub path: String, // blueprint written by the train binary
+    // of the bets that aren't in the abstraction
+    #[serde(default)]
+    pub translation: ActionTranslation,
+}
+
+/// Plays a blueprint strategy trained on heads-up no-limit hold'em. The actions of the hand
+/// are translated into the betting abstraction to find the bot's info set, then the abstract
+/// action it samples is sized to the real pot. checks or calls when the hand leaves the
+/// abstraction, and in the other variants
+pub struct BlueprintBot {
+    blueprint: Blueprint,
+    cards: CardAbstraction,
+    translation: ActionTranslation,
+    // actions of the hand with the abstract action they were translated to, so a random
+    // translation stays the same for the rest of the hand
+    translated: Vec<(ActionRecord, AbstractAction)>,
+    rng: StdRng,
+}
+impl BlueprintBot {
+    /// loads the blueprint and the bucket tables it was trained with
+    pub fn new(cfg: &BlueprintCfg, seed: Option<u64>) -> Result<BlueprintBot, String> {
+        let blueprint = Blueprint::load(Path::new(&cfg.path))?;
+        let cards = CardAbstraction::new(&blueprint.get_cfg().cards)?;
+        if cards.get_hash() != blueprint.get_card_hash() {
+            return Err(format!(
+                "The bucket tables changed since {} was trained",
+                cfg.path
+            ));
+        }
+        Ok(BlueprintBot {
+            cards,
+            blueprint,
+            translation: cfg.translation,
+            translated: Vec::new(),
+            rng: match seed {
+                Some(seed) => StdRng::seed_from_u64(seed),
+                None => StdRng::from_entropy(),
+            },
+        })
+    }
+
+    /// the abstract action of an action of the hand, bets by their fraction of the pot
+    fn translate(&mut self, betting: &BettingState, record: &ActionRecord) -> AbstractAction {
+        let abstraction = &self.blueprint.get_cfg().betting;
+        let amount = match record.get_action() {
+            Action::Fold
+                if betting
+                    .get_actions(abstraction)
+                    .contains(&AbstractAction::Fold) =>
+            {
+                return AbstractAction::Fold;
+            }
+            Action::Bet(amount) | Action::Raise(amount) => amount,
+            _ => return AbstractAction::Call,
+        };
+        let fraction = amount as f64 / (record.get_pot() + record.get_to_call()) as f64;
+        betting.translate(abstraction, self.translation, fraction, &mut self.rng)
+    }
+
+    /// the betting of the hand in the abstraction, None if it can't be followed
+    fn get_betting(&mut self, view: &SeatView) -> Option<BettingState> {
+        let cfg = self.blueprint.get_cfg();
+        let mut betting =
+            BettingState::new(cfg.stack, cfg.small_blind_amount, cfg.big_blind_amount).ok()?;
+        // forget the translations of another hand
+        let history = view.get_history();
+        let same = self
+            .translated
+            .iter()
+            .zip(history)
+            .take_while(|((translated, _), record)| translated == *record)
+            .count();
+        self.translated.truncate(same);
+        for (index, record) in history.iter().enumerate() {
+            if betting.is_over() || betting.get_betting_round() != record.get_betting_round() {
+                return None;
+            }
+            let action = match self.translated.get(index) {
+                Some(&(_, action)) => action,
+                None => {
+                    let action = self.translate(&betting, record);
+                    self.translated.push((record.clone(), action));
+                    action
+                }
+            };
+            betting = betting.apply(&self.blueprint.get_cfg().betting, action);
+        }
+        Some(betting).filter(|betting| betting.get_betting_round() == view.get_betting_round())
+    }
+
+    /// the abstract action sized to the pot of the hand
+    fn to_action(&self, view: &SeatView, betting: &BettingState, action: AbstractAction) -> Action {
+        let range = view.get_raise_or_bet_range();
+        let amount = match (action, range) {
+            (AbstractAction::Fold, _) if view.get_legal_actions().contains(&Action::Fold) => {
+                return Action::Fold;
+            }
+            (AbstractAction::Bet(index), Some(range)) => {
+                let fraction =
+                    betting.get_bet_sizes(&self.blueprint.get_cfg().betting)[index as usize];
+                let to_call = view.get_call_amount().unwrap_or(0);
+                let pot = view.get_pot_size() + view.get_bet_sizes().iter().sum::<usize>();
+                let amount = (fraction * (pot + to_call) as f64).round() as usize;
+                amount.clamp(*range.start(), *range.end())
+            }
+            (AbstractAction::AllIn, Some(range)) => *range.end(),
+            _ => return check_or_call(view),
+        };
+        if view.get_legal_actions().contains(&Action::Bet(0)) {
+            Action::Bet(amount)
+        } else {
+            Action::Raise(amount)
+        }
+    }
+}
+impl Agent for BlueprintBot {
+    fn act(&mut self, view: &SeatView) -> Action {
+        if view.is_discarding() {
+            return keep_best_hole(view);
+        }
+        if view.get_variant() != self.blueprint.get_variant() {
+            return check_or_call(view);
+        }
+        let betting = match self.get_betting(view) {
+            Some(betting) => betting,
+            None => return check_or_call(view),
+        };
+        // the dealer is the first player of the abstraction
+        let player = if view.get_seat_index() == view.get_dealer_index() {
+            0
+        } else {
+            1
+        };
+        if betting.get_player() != Some(player) {
+            return check_or_call(view);
+        }
+        let bucket = self.cards.get_bucket(
+            view.get_betting_round(),
+            view.get_hole(),
+            view.get_community(),
+        );
+        let actions = betting.get_actions(&self.blueprint.get_cfg().betting);
+        let strategy = match self
+            .blueprint
+            .get_strategy(&HoldemTree::get_key(bucket, &betting))
+        {
+            Some(strategy) if strategy.len() == actions.len() => strategy,
+            _ => return check_or_call(view),
+        };
+        // sample an action by its probability
+        let mut target: f32 = self.rng.gen();
+        let index = strategy
+            .iter()
+            .position(|&probability| {
+                target -= probability;
+                target < 0.0
+            })
+            .unwrap_or(actions.len() - 1);
+        let action = self.to_action(view, &betting, actions[index]);
+        // the bot's own action is the abstract action it picked, whatever its size
+        self.translated.push((
+            ActionRecord {
+                betting_round: view.get_betting_round(),
+                player_index: view.get_seat_index(),
+                action,
+                pot: view.get_pot_size() + view.get_bet_sizes().iter().sum::<usize>(),
+                to_call: view.get_call_amount().unwrap_or(0),
+            },
+            actions[index],
+        ));
+        action
+    }
+    fn hand_finished(&mut self, _seat_index: usize, _result: &HandResult) {
+        self.translated.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::super::holdem_tree::HoldemTreeCfg;
+    use super::super::super::mccfr::{Mccfr, MccfrCfg};
+    use super::super::super::{agent::Table, Game};
+    use super::super::RandomBot;
+    use super::*;
+
+    #[test]
+    fn follows_the_hand() {
+        let tree = HoldemTree::new(HoldemTreeCfg::default()).unwrap();
+        let mut mccfr = Mccfr::new(tree, MccfrCfg::default()).unwrap();
+        mccfr.iterate(200);
+        let path = std::env::temp_dir().join("blueprint_bot_test.bin");
+        Blueprint::new(&mccfr).unwrap().save(&path).unwrap();
+        let cfg = BlueprintCfg {
+            path: path.to_str().unwrap().into(),
+            translation: ActionTranslation::PseudoHarmonic,
+        };
+        let mut bot = BlueprintBot::new(&cfg, Some(0)).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        // the same blinds and stacks as the abstraction, a pot raise is in it
+        let cfg = r#"{
+            "player_name": ["A", "B"],
+            "starting_chip": [200, 200],
+            "small_blind_amount": 1,
+            "big_blind_amount": 2,
+            "first_dealer_index": 0
+        }"#;
+        let game = Game::new(serde_json::from_str(cfg).unwrap()).unwrap();
+        let game = game.act(Action::Raise(4)).unwrap();
+        let betting = bot.get_betting(&SeatView::new(&game)).unwrap();
+        assert_eq!(betting.get_history(), [3]);
+        // a raise between a pot raise and all-in is translated to one of them at random,
+        // then the same way for the rest of the hand
+        let game = game.act(Action::Raise(60)).unwrap();
+        let view = SeatView::new(&game);
+        let history = bot.get_betting(&view).unwrap().get_history().to_vec();
+        assert!(history == [3, 3] || history == [3, 2]);
+        for _ in 0..10 {
+            assert_eq!(bot.get_betting(&view).unwrap().get_history(), history);
+        }
+        // calling all-in ends the hand in the abstraction, calling a pot raise goes to the flop
+        let game = game.act(Action::Call).unwrap();
+        let betting = bot.get_betting(&SeatView::new(&game));
+        assert_eq!(betting.is_none(), history == [3, 2]);
+
+        // it picks legal actions with other blinds and stacks too
+        let cfg = r#"{
+            "player_name": ["A", "B"],
+            "starting_chip": [1000, 1500],
+            "small_blind_amount": 5,
+            "big_blind_amount": 10,
+            "first_dealer_index": 1
+        }"#;
+        let game = Game::new(serde_json::from_str(cfg).unwrap()).unwrap();
+        let agents: Vec<Option<Box<dyn Agent + Send>>> =
+            vec![Some(Box::new(bot)), Some(Box::new(RandomBot::new(Some(0))))];
+        let mut table = Table::new(game, agents).unwrap();
+        for _ in 0..500 {
+            if !table.step().unwrap() {
+                break;
+            }
+        }
+    }
+}
@@ -0,0 +1,12 @@
+use super::{check_or_call, keep_best_hole, Action, Agent, SeatView};
+
+/// calls every bet and checks otherwise, never bets, raises or folds
+pub struct CallingStation;
+impl Agent for CallingStation {
+    fn act(&mut self, view: &SeatView) -> Action {
+        if view.is_discarding() {
+            return keep_best_hole(view);
+        }
+        check_or_call(view)
+    }
+}
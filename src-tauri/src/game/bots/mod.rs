@@ -0,0 +1,124 @@
+//! Bots that can be put in a seat from poker.conf.json
+
+mod blueprint;
+mod calling_station;
+mod random;
+mod tag;
+
+use serde::{Deserialize, Serialize};
+
+use super::agent::{Agent, HandResult, SeatView};
+use super::card::evaluate;
+use super::Action;
+
+pub use self::blueprint::{BlueprintBot, BlueprintCfg};
+pub use self::calling_station::CallingStation;
+pub use self::random::RandomBot;
+pub use self::tag::{TagBot, TagCfg};
+
+/// which bot plays a seat
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub enum BotCfg {
+    Random, // any legal action, uniformly
+    CallingStation,
+    Tag(TagCfg),             // tight-aggressive, see `TagBot`
+    Blueprint(BlueprintCfg), // trained with Monte Carlo CFR, see `BlueprintBot`
+}
+impl BotCfg {
+    /// makes the bot, `seed` makes its choices repeatable, random if None.
+    /// returns error if the bot's files can't be loaded
+    pub fn new_agent(&self, seed: Option<u64>) -> Result<Box<dyn Agent + Send>, String> {
+        Ok(match self {
+            BotCfg::Random => Box::new(RandomBot::new(seed)),
+            BotCfg::CallingStation => Box::new(CallingStation),
+            BotCfg::Tag(cfg) => Box::new(TagBot::new(cfg.clone())),
+            BotCfg::Blueprint(cfg) => Box::new(BlueprintBot::new(cfg, seed)?),
+        })
+    }
+}
+
+/// calls if there's a bet, checks otherwise
+fn check_or_call(view: &SeatView) -> Action {
+    if view.get_legal_actions().contains(&Action::Call) {
+        Action::Call
+    } else {
+        Action::Check
+    }
+}
+
+/// discards the hole card that leaves the best hand with the community,
+/// the best pair or high cards before the flop
+fn keep_best_hole(view: &SeatView) -> Action {
+    let hole = view.get_hole();
+    let index = (0..hole.len())
+        .max_by_key(|&discarded| {
+            evaluate(
+                hole.iter()
+                    .enumerate()
+                    .filter(|&(index, _)| index != discarded)
+                    .map(|(_, card)| card)
+                    .chain(view.get_community())
+                    .collect(),
+            )
+        })
+        .unwrap();
+    Action::Discard(index)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::{agent::Table, Game};
+    use super::*;
+
+    #[test]
+    fn bots_play_legal_actions() {
+        let bots = [
+            BotCfg::Random,
+            BotCfg::CallingStation,
+            BotCfg::Tag(TagCfg::default()),
+        ];
+        for variant in [
+            "NoLimitHoldem",
+            "LimitHoldem",
+            "Pineapple",
+            "CrazyPineapple",
+        ] {
+            for (bot, opponent) in bots.iter().zip(bots.iter().cycle().skip(1)) {
+                let new_game = || {
+                    let cfg = format!(
+                        r#"{{
+                            "player_name": ["A", "B"],
+                            "starting_chip": [1000, 1000],
+                            "small_blind_amount": 5,
+                            "big_blind_amount": 10,
+                            "first_dealer_index": 0,
+                            "variant": "{}"
+                        }}"#,
+                        variant
+                    );
+                    Game::new(serde_json::from_str(&cfg).unwrap()).unwrap()
+                };
+                let agents = vec![
+                    Some(bot.new_agent(Some(0)).unwrap()),
+                    Some(opponent.new_agent(Some(1)).unwrap()),
+                ];
+                let mut table = Table::new(new_game(), agents).unwrap();
+                // errors if a bot picks an illegal action
+                for _ in 0..500 {
+                    if !table.step().unwrap() {
+                        // start over when someone runs out of chips
+                        assert!(table.get_game().is_session_over());
+                        table.set_game(new_game());
+                    }
+                    let game = table.get_game();
+                    let chips: usize = game
+                        .players
+                        .iter()
+                        .map(|player| player.stack + player.bet_size)
+                        .sum();
+                    assert_eq!(chips + game.pot_size, 2000);
+                }
+            }
+        }
+    }
+}
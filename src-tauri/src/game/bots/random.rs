@@ -0,0 +1,39 @@
+use rand::{rngs::StdRng, seq::SliceRandom, Rng, SeedableRng};
+
+use super::{Action, Agent, SeatView};
+
+/// picks a legal action uniformly, then a uniform amount for bets and raises
+pub struct RandomBot {
+    rng: StdRng,
+}
+impl RandomBot {
+    pub fn new(seed: Option<u64>) -> RandomBot {
+        RandomBot {
+            rng: match seed {
+                Some(seed) => StdRng::seed_from_u64(seed),
+                None => StdRng::from_entropy(),
+            },
+        }
+    }
+}
+impl Agent for RandomBot {
+    fn act(&mut self, view: &SeatView) -> Action {
+        let action = *view
+            .get_legal_actions()
+            .choose(&mut self.rng)
+            .expect("No legal actions");
+        match action {
+            Action::Bet(_) | Action::Raise(_) => {
+                let amount = self
+                    .rng
+                    .gen_range(view.get_raise_or_bet_range().expect("Can't bet or raise"));
+                if let Action::Bet(_) = action {
+                    Action::Bet(amount)
+                } else {
+                    Action::Raise(amount)
+                }
+            }
+            _ => action,
+        }
+    }
+}
@@ -0,0 +1,202 @@
+use itertools::Itertools;
+use serde::{Deserialize, Serialize};
+
+use super::super::card::{Card, HandType, Range};
+use super::super::BettingRound;
+use super::{check_or_call, keep_best_hole, Action, Agent, SeatView};
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(default)]
+pub struct TagCfg {
+    pub open_range: Range,      // raised first in on the button
+    pub defend_range: Range,    // called against a raise
+    pub three_bet_range: Range, // re-raised against a raise
+    pub open_size: f64,         // raise to this many big blinds before the flop
+    pub bet_size: f64,          // fraction of the pot bet or raised by after the flop
+    // estimated equity of nothing, a weak pair, top pair and better hands,
+    // calls a bet after the flop if it's more than the pot odds
+    pub equities: [f64; 4],
+}
+impl Default for TagCfg {
+    fn default() -> Self {
+        let range = |s: &str| s.parse().unwrap();
+        TagCfg {
+            open_range: range("22+, A2s+, K7s+, Q8s+, J8s+, T8s+, 98s, 87s, A7o+, K9o+, QTo+, JTo"),
+            defend_range: range(
+                "22+, A2s+, K2s+, Q5s+, J7s+, T7s+, 97s+, 86s+, 76s, A2o+, K8o+, Q9o+, J9o+, T9o",
+            ),
+            three_bet_range: range("99+, AJs+, KQs, AQo+"),
+            open_size: 2.5,
+            bet_size: 0.66,
+            equities: [0.1, 0.35, 0.6, 0.85],
+        }
+    }
+}
+
+/// how good a made hand is after the flop, compared to the community
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Debug)]
+enum Strength {
+    Nothing, // the hole cards don't improve the community
+    WeakPair,
+    TopPair, // or an overpair
+    Strong,  // two pair or better made with the hole cards
+}
+impl Strength {
+    fn new(hole: [Card; 2], community: &[Card]) -> Strength {
+        let made = HandType::from_cards(&[&hole[..], community].concat());
+        let board = HandType::from_cards(community);
+        if made <= board {
+            return Strength::Nothing;
+        }
+        match (made, board) {
+            // just a higher card than the community
+            (HandType::HighCard(_), _) => Strength::Nothing,
+            (HandType::OnePair(_), HandType::HighCard(high)) if made >= HandType::OnePair(high) => {
+                Strength::TopPair
+            }
+            (HandType::OnePair(_), _) => Strength::WeakPair,
+            // eg. higher trips or a higher flush than the community's
+            _ if made.cmp_category(&board).is_eq() => Strength::WeakPair,
+            // one pair with the hole cards on a paired community
+            (HandType::TwoPair(_, _), HandType::OnePair(_)) => Strength::TopPair,
+            _ => Strength::Strong,
+        }
+    }
+}
+
+/// Rule-based tight-aggressive bot. Plays few hands before the flop by their hand class
+/// and position, then bets its made hands and calls when its estimated equity beats the pot odds
+pub struct TagBot {
+    cfg: TagCfg,
+}
+impl TagBot {
+    pub fn new(cfg: TagCfg) -> TagBot {
+        TagBot { cfg }
+    }
+
+    /// every two of the hole cards, there're three pairs before discarding
+    fn get_holes(view: &SeatView) -> Vec<[Card; 2]> {
+        view.get_hole()
+            .iter()
+            .copied()
+            .tuple_combinations()
+            .map(|(a, b)| [a, b])
+            .collect()
+    }
+
+    /// whether the range has any of the hole pairs
+    fn in_range(range: &Range, holes: &[[Card; 2]]) -> bool {
+        holes.iter().any(|&hole| range.get_weight(hole) > 0.0)
+    }
+
+    /// raises or bets so the bet is `amount` more than the previous bet,
+    /// within the legal amounts. calls or checks if it can't
+    fn aggress(view: &SeatView, amount: f64) -> Action {
+        let range = match view.get_raise_or_bet_range() {
+            Some(range) => range,
+            None => return check_or_call(view),
+        };
+        let amount = (amount.round() as usize).clamp(*range.start(), *range.end());
+        if view.get_legal_actions().contains(&Action::Bet(0)) {
+            Action::Bet(amount)
+        } else {
+            Action::Raise(amount)
+        }
+    }
+
+    fn pre_flop(&self, view: &SeatView, holes: &[[Card; 2]]) -> Action {
+        let big_blind_amount = view.get_big_blind_amount() as f64;
+        let previous_bet = *view.get_bet_sizes().iter().max().unwrap() as f64;
+        let open_amount = self.cfg.open_size * big_blind_amount - previous_bet;
+        if previous_bet > big_blind_amount {
+            // facing a raise, re-raise to three times the bet
+            if TagBot::in_range(&self.cfg.three_bet_range, holes) {
+                return TagBot::aggress(view, previous_bet * 2.0);
+            }
+            if TagBot::in_range(&self.cfg.defend_range, holes) {
+                return Action::Call;
+            }
+            return Action::Fold;
+        }
+        // first in on the button, or in the big blind after a limp
+        if TagBot::in_range(&self.cfg.open_range, holes) {
+            // raise the premium hands from the big blind
+            let in_big_blind = view.get_seat_index() != view.get_dealer_index();
+            if !in_big_blind || TagBot::in_range(&self.cfg.three_bet_range, holes) {
+                return TagBot::aggress(view, open_amount);
+            }
+        }
+        if view.get_legal_actions().contains(&Action::Check) {
+            Action::Check
+        } else {
+            Action::Fold
+        }
+    }
+
+    fn post_flop(&self, view: &SeatView, holes: &[[Card; 2]]) -> Action {
+        let strength = holes
+            .iter()
+            .map(|&hole| Strength::new(hole, view.get_community()))
+            .max()
+            .unwrap();
+        let pot = view.get_pot_size() + view.get_bet_sizes().iter().sum::<usize>();
+        let bet_amount = self.cfg.bet_size * pot as f64;
+        // the dealer acts last after the flop heads up
+        let in_position = view.get_seat_index() == view.get_dealer_index();
+        match view.get_call_amount() {
+            Some(call_amount) => {
+                if strength == Strength::Strong {
+                    return TagBot::aggress(view, bet_amount);
+                }
+                let pot_odds = call_amount as f64 / (pot + call_amount) as f64;
+                if self.cfg.equities[strength as usize] >= pot_odds {
+                    Action::Call
+                } else {
+                    Action::Fold
+                }
+            }
+            None => match strength {
+                Strength::Strong | Strength::TopPair => TagBot::aggress(view, bet_amount),
+                Strength::WeakPair if in_position => TagBot::aggress(view, bet_amount),
+                _ => Action::Check,
+            },
+        }
+    }
+}
+impl Agent for TagBot {
+    fn act(&mut self, view: &SeatView) -> Action {
+        if view.is_discarding() {
+            return keep_best_hole(view);
+        }
+        let holes = TagBot::get_holes(view);
+        match view.get_betting_round() {
+            BettingRound::PreFlop => self.pre_flop(view, &holes),
+            _ => self.post_flop(view, &holes),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cards(s: &str) -> Vec<Card> {
+        Card::parse_list(s).unwrap()
+    }
+
+    #[test]
+    fn strengths() {
+        let strength = |hole: &str, community: &str| {
+            Strength::new(cards(hole).try_into().unwrap(), &cards(community))
+        };
+        assert_eq!(strength("Ah Kd", "Ks 7c 2d"), Strength::TopPair);
+        assert_eq!(strength("Qh Qd", "Js 7c 2d"), Strength::TopPair);
+        assert_eq!(strength("7h 6d", "Ks 7c 2d"), Strength::WeakPair);
+        assert_eq!(strength("Ah Qd", "Ks 7c 2d"), Strength::Nothing);
+        assert_eq!(strength("Kh 7d", "Ks 7c 2d"), Strength::Strong);
+        assert_eq!(strength("9h 8d", "Ts 7c 6d"), Strength::Strong);
+        // the pair on the community isn't the bot's
+        assert_eq!(strength("Ah 3d", "Ks Kc 2d"), Strength::Nothing);
+        assert_eq!(strength("Ah 2h", "Ks Kc 2d"), Strength::TopPair);
+    }
+}
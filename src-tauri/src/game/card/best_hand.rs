@@ -0,0 +1,168 @@
+use itertools::Itertools;
+use serde::{Deserialize, Serialize};
+
+use super::handtype::HandType;
+use super::{evaluate, tie_break_order, Card, Hand, Rank};
+
+/// The best five cards a player can make, used to show which cards played at showdown.
+/// Before the river there can be less than five cards, eg. only the hole cards preflop
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct BestHand {
+    cards: Vec<Card>,     // most important first, eg. the pair then the kickers
+    hole_used: Vec<Card>, // hole cards that are part of the five cards
+    hand_type: HandType,
+    description: String, // eg. "Pair of Kings, Ace-Queen-Nine kickers"
+}
+impl BestHand {
+    /// find the best five cards out of the hole and community,
+    /// or use all of them if there're five or less. needs 2 to 7 cards in total
+    pub fn find(hole: &[Card], community: &[Card]) -> BestHand {
+        let all_cards: Vec<Card> = hole.iter().chain(community).copied().collect();
+        let best_cards = if all_cards.len() <= 5 {
+            all_cards
+        } else {
+            all_cards
+                .into_iter()
+                .combinations(5)
+                .max_by_key(|cards| evaluate(cards.iter().collect()))
+                .unwrap()
+        };
+        let hand_type = HandType::from_cards(&best_cards);
+        let cards = tie_break_order(&best_cards);
+        let ranks: Vec<Rank> = cards.iter().map(|card| card.rank).collect();
+        BestHand {
+            hole_used: cards
+                .iter()
+                .filter(|card| hole.contains(card))
+                .copied()
+                .collect(),
+            hand_type,
+            description: describe(hand_type, &ranks),
+            cards,
+        }
+    }
+    pub fn get_cards(&self) -> &[Card] {
+        &self.cards
+    }
+    pub fn get_hole_used(&self) -> &[Card] {
+        &self.hole_used
+    }
+    pub fn get_hand_type(&self) -> HandType {
+        self.hand_type
+    }
+    pub fn get_description(&self) -> &str {
+        &self.description
+    }
+}
+
+/// full description of a hand from its ranks ordered by how they break ties.
+/// hands with less than five cards have less kickers
+fn describe(hand_type: HandType, ranks: &[Rank]) -> String {
+    // joins the ranks with dashes, eg. "Ace-Queen-Nine"
+    let join = |ranks: &[Rank]| ranks.iter().map(|&rank| Card::display_rank(rank)).join("-");
+    // the hand without kickers, and the number of ranks used by it
+    let (name, num_rank_used) = match hand_type {
+        HandType::RoyalFlush => ("Royal Flush".into(), 5),
+        HandType::StraightFlush(r) => {
+            (format!("Straight Flush, {} high", Card::display_rank(r)), 5)
+        }
+        HandType::FourOfAKind(r) => (
+            format!("Four of a Kind, {}", Card::display_rank_plural(r)),
+            4,
+        ),
+        HandType::FullHouse(tr, pr) => (
+            format!(
+                "Full House, {} full of {}",
+                Card::display_rank_plural(tr),
+                Card::display_rank_plural(pr)
+            ),
+            5,
+        ),
+        HandType::Flush => (format!("Flush, {}", join(ranks)), 5),
+        HandType::Straight(r) => (format!("Straight, {} high", Card::display_rank(r)), 5),
+        HandType::ThreeOfAKind(r) => (
+            format!("Three of a Kind, {}", Card::display_rank_plural(r)),
+            3,
+        ),
+        HandType::TwoPair(p1r, p2r) => (
+            format!(
+                "Two Pair, {} and {}",
+                Card::display_rank_plural(p1r),
+                Card::display_rank_plural(p2r)
+            ),
+            4,
+        ),
+        HandType::OnePair(r) => (format!("Pair of {}", Card::display_rank_plural(r)), 2),
+        HandType::HighCard(_) => (format!("High Card, {}", join(ranks)), 5),
+    };
+    match ranks.get(num_rank_used..).unwrap_or(&[]) {
+        [] => name,
+        [kicker] => format!("{}, {} kicker", name, Card::display_rank(*kicker)),
+        kickers => format!("{}, {} kickers", name, join(kickers)),
+    }
+}
+
+impl Hand {
+    /// full description of the hand including kickers,
+    /// eg. "Pair of Kings, Ace-Queen-Nine kickers" or "Full House, Kings full of Threes"
+    pub fn get_description(self) -> String {
+        let ranks = self.get_tie_break_order().map(|card| card.rank);
+        describe(self.hand_type, &ranks)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn best_hand_tests() {
+        let hole = Card::parse_list("Kh 4c").unwrap();
+        let community = Card::parse_list("Ks As Qd 9c 2h").unwrap();
+        let best_hand = BestHand::find(&hole, &community);
+        assert_eq!(Card::format_list(best_hand.get_cards()), "Ks Kh As Qd 9c");
+        assert_eq!(best_hand.get_hole_used(), &hole[..1]);
+        assert_eq!(best_hand.get_hand_type(), HandType::OnePair(13));
+        assert_eq!(
+            best_hand.get_description(),
+            "Pair of Kings, Ace-Queen-Nine kickers"
+        );
+
+        // the board plays
+        let hole = Card::parse_list("2c 3d").unwrap();
+        let community = Card::parse_list("Ts Js Qs Ks As").unwrap();
+        let best_hand = BestHand::find(&hole, &community);
+        assert!(best_hand.get_hole_used().is_empty());
+        assert_eq!(best_hand.get_description(), "Royal Flush");
+
+        let hole = Card::parse_list("3h 3c").unwrap();
+        let community = Card::parse_list("Kd Ks 5s Kc 6h").unwrap();
+        let best_hand = BestHand::find(&hole, &community);
+        assert_eq!(
+            best_hand.get_description(),
+            "Full House, Kings full of Threes"
+        );
+        assert_eq!(best_hand.get_hole_used().len(), 2);
+
+        // before the river
+        let best_hand = BestHand::find(&hole, &[]);
+        assert_eq!(best_hand.get_description(), "Pair of Threes");
+        let best_hand = BestHand::find(&hole, &community[..3]);
+        assert_eq!(best_hand.get_hand_type(), HandType::TwoPair(13, 3));
+        assert_eq!(
+            best_hand.get_description(),
+            "Two Pair, Kings and Threes, Five kicker"
+        );
+        let hole = Card::parse_list("Ah 7c").unwrap();
+        let best_hand = BestHand::find(&hole, &community[..4]);
+        assert_eq!(best_hand.get_cards().len(), 5);
+        assert_eq!(
+            best_hand.get_description(),
+            "Three of a Kind, Kings, Ace-Seven kickers"
+        );
+        assert_eq!(
+            HandType::from_cards(&Card::parse_list("Ah 7c").unwrap()),
+            HandType::HighCard(1)
+        );
+    }
+}
@@ -0,0 +1,240 @@
+use std::ops::{BitAnd, BitOr, Not, Sub};
+
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+
+use super::Card;
+
+/// Set of cards stored as a 52 bit mask.
+/// Bit `13 * suit + rank index` is set if the card is in the set,
+/// so each suit's ranks are a contiguous 13 bit mask.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct CardSet(u64); // fits in 52 bits so it's still exact as a javascript number
+
+impl CardSet {
+    pub const EMPTY: CardSet = CardSet(0);
+    pub const FULL: CardSet = CardSet((1 << 52) - 1);
+
+    pub fn new() -> CardSet {
+        CardSet::EMPTY
+    }
+    pub fn contains(self, card: Card) -> bool {
+        self.0 & CardSet::bit(card) != 0
+    }
+    /// add card to the set, returns false if it's already in the set
+    pub fn insert(&mut self, card: Card) -> bool {
+        let contained = self.contains(card);
+        self.0 |= CardSet::bit(card);
+        !contained
+    }
+    /// remove card from the set, returns false if it's not in the set
+    pub fn remove(&mut self, card: Card) -> bool {
+        let contained = self.contains(card);
+        self.0 &= !CardSet::bit(card);
+        contained
+    }
+    pub fn union(self, other: CardSet) -> CardSet {
+        CardSet(self.0 | other.0)
+    }
+    pub fn intersection(self, other: CardSet) -> CardSet {
+        CardSet(self.0 & other.0)
+    }
+    pub fn difference(self, other: CardSet) -> CardSet {
+        CardSet(self.0 & !other.0)
+    }
+    pub fn is_disjoint(self, other: CardSet) -> bool {
+        self.0 & other.0 == 0
+    }
+    pub fn len(self) -> usize {
+        self.0.count_ones() as usize
+    }
+    pub fn is_empty(self) -> bool {
+        self.0 == 0
+    }
+    /// returns the 13 bit rank mask of a suit, bit 0 is deuce and bit 12 is ace
+    pub fn get_suit_mask(self, suit_index: usize) -> u16 {
+        (self.0 >> (13 * suit_index) & 0x1fff) as u16
+    }
+    pub fn iter(self) -> CardSetIter {
+        CardSetIter(self.0)
+    }
+    /// returns a uniformly random card from the set, None if the set is empty
+    pub fn random_card<R: Rng + ?Sized>(self, rng: &mut R) -> Option<Card> {
+        if self.is_empty() {
+            return None;
+        }
+        // clear the lowest bits until the chosen card is the lowest
+        let mut bits = self.0;
+        for _ in 0..rng.gen_range(0..self.len()) {
+            bits &= bits - 1;
+        }
+        Some(Card::from_index(bits.trailing_zeros() as u8))
+    }
+    /// removes and returns a uniformly random card from the set, None if the set is empty
+    pub fn draw<R: Rng + ?Sized>(&mut self, rng: &mut R) -> Option<Card> {
+        let card = self.random_card(rng)?;
+        self.remove(card);
+        Some(card)
+    }
+    /// returns `num_card` random cards from the full deck that aren't in `excluded`
+    pub fn draw_excluding<R: Rng + ?Sized>(
+        excluded: CardSet,
+        num_card: usize,
+        rng: &mut R,
+    ) -> CardSet {
+        let mut remaining = !excluded;
+        let mut drawn = CardSet::EMPTY;
+        for _ in 0..num_card {
+            drawn.insert(remaining.draw(rng).expect("Not enough cards left to draw"));
+        }
+        drawn
+    }
+    /// calls `f` with every subset of the set that has `size` cards
+    pub fn for_each_subset<F: FnMut(CardSet)>(self, size: usize, f: &mut F) {
+        fn recurse<F: FnMut(CardSet)>(remaining: u64, size: usize, chosen: CardSet, f: &mut F) {
+            if size == 0 {
+                f(chosen);
+                return;
+            }
+            let mut remaining = remaining;
+            // choose the lowest card then the rest from the higher cards
+            while remaining.count_ones() as usize >= size {
+                let lowest = remaining & remaining.wrapping_neg();
+                remaining &= remaining - 1;
+                recurse(remaining, size - 1, CardSet(chosen.0 | lowest), f);
+            }
+        }
+        recurse(self.0, size, CardSet::EMPTY, f);
+    }
+    fn bit(card: Card) -> u64 {
+        1 << card.get_index()
+    }
+}
+impl From<Card> for CardSet {
+    fn from(card: Card) -> Self {
+        CardSet(CardSet::bit(card))
+    }
+}
+impl FromIterator<Card> for CardSet {
+    fn from_iter<T: IntoIterator<Item = Card>>(iter: T) -> Self {
+        let mut set = CardSet::EMPTY;
+        for card in iter {
+            set.insert(card);
+        }
+        set
+    }
+}
+impl<'a> FromIterator<&'a Card> for CardSet {
+    fn from_iter<T: IntoIterator<Item = &'a Card>>(iter: T) -> Self {
+        iter.into_iter().copied().collect()
+    }
+}
+impl IntoIterator for CardSet {
+    type Item = Card;
+    type IntoIter = CardSetIter;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+impl BitOr for CardSet {
+    type Output = CardSet;
+
+    fn bitor(self, rhs: Self) -> Self::Output {
+        self.union(rhs)
+    }
+}
+impl BitAnd for CardSet {
+    type Output = CardSet;
+
+    fn bitand(self, rhs: Self) -> Self::Output {
+        self.intersection(rhs)
+    }
+}
+impl Sub for CardSet {
+    type Output = CardSet;
+
+    fn sub(self, rhs: Self) -> Self::Output {
+        self.difference(rhs)
+    }
+}
+impl Not for CardSet {
+    type Output = CardSet;
+
+    /// all the cards in a deck that aren't in the set
+    fn not(self) -> Self::Output {
+        CardSet::FULL.difference(self)
+    }
+}
+
+/// iterates the cards of a `CardSet` from the lowest bit
+pub struct CardSetIter(u64);
+impl Iterator for CardSetIter {
+    type Item = Card;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.0 == 0 {
+            return None;
+        }
+        let index = self.0.trailing_zeros();
+        // clear the lowest bit
+        self.0 &= self.0 - 1;
+        Some(Card::from_index(index as u8))
+    }
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let len = self.0.count_ones() as usize;
+        (len, Some(len))
+    }
+}
+impl ExactSizeIterator for CardSetIter {}
+
+#[cfg(test)]
+mod tests {
+    use super::super::Suit;
+    use super::*;
+    use rand::{rngs::StdRng, SeedableRng};
+
+    #[test]
+    fn set_operations() {
+        let ace_of_spades = Card::new(1, Suit::Spade);
+        let two_of_hearts = Card::new(2, Suit::Heart);
+        let mut set = CardSet::new();
+        assert!(set.insert(ace_of_spades));
+        assert!(!set.insert(ace_of_spades));
+        assert!(set.contains(ace_of_spades) && !set.contains(two_of_hearts));
+
+        let other: CardSet = [ace_of_spades, two_of_hearts].iter().collect();
+        assert_eq!(set | other, other);
+        assert_eq!(set & other, set);
+        assert_eq!(
+            (other - set).iter().collect::<Vec<Card>>(),
+            vec![two_of_hearts]
+        );
+        assert_eq!((!other).len(), 50);
+        assert_eq!(CardSet::FULL.iter().count(), 52);
+        assert!(CardSet::FULL
+            .iter()
+            .all(|card| CardSet::FULL.contains(card)));
+    }
+
+    #[test]
+    fn subsets() {
+        let mut count = 0;
+        CardSet::FULL.for_each_subset(3, &mut |subset| {
+            assert_eq!(subset.len(), 3);
+            count += 1;
+        });
+        assert_eq!(count, 22100);
+    }
+
+    #[test]
+    fn draw_excluding() {
+        let mut rng = StdRng::seed_from_u64(0);
+        let excluded = CardSet::draw_excluding(CardSet::EMPTY, 10, &mut rng);
+        assert_eq!(excluded.len(), 10);
+        for _ in 0..100 {
+            let drawn = CardSet::draw_excluding(excluded, 42, &mut rng);
+            assert_eq!(drawn, !excluded);
+        }
+    }
+}
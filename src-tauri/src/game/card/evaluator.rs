@@ -0,0 +1,247 @@
+use super::handtype::HandType;
+use super::{CardSet, Rank};
+
+/// 13 bit mask of ranks, bit 0 is deuce and bit 12 is ace
+type RankMask = u16;
+
+const NUM_RANK_MASKS: usize = 1 << 13;
+
+// strength is laid out as category << CATEGORY_SHIFT | ranks of the category,
+// so comparing strengths compares the category first, then the ranks
+const CATEGORY_SHIFT: u32 = 26;
+const HIGH_CARD: u32 = 0;
+const ONE_PAIR: u32 = 1;
+const TWO_PAIR: u32 = 2;
+const THREE_OF_A_KIND: u32 = 3;
+const STRAIGHT: u32 = 4;
+const FLUSH: u32 = 5;
+const FULL_HOUSE: u32 = 6;
+const FOUR_OF_A_KIND: u32 = 7;
+const STRAIGHT_FLUSH: u32 = 8;
+
+/// maps a rank mask to 1 + the rank index of the highest card of its best straight.
+/// 0 if the mask doesn't contain a straight
+static STRAIGHT_TABLE: [u8; NUM_RANK_MASKS] = build_straight_table();
+/// maps a rank mask to the mask of its 5 highest ranks
+static TOP_FIVE_TABLE: [RankMask; NUM_RANK_MASKS] = build_top_five_table();
+
+const fn build_straight_table() -> [u8; NUM_RANK_MASKS] {
+    let mut table = [0; NUM_RANK_MASKS];
+    let mut mask = 0;
+    while mask < NUM_RANK_MASKS {
+        // check from the highest straight down
+        let mut high = 12;
+        while high >= 4 {
+            let straight = 0b11111 << (high - 4);
+            if mask & straight == straight {
+                table[mask] = high as u8 + 1;
+                break;
+            }
+            high -= 1;
+        }
+        // 5-4-3-2-A, the ace plays low
+        let wheel = 0b1_0000_0000_1111;
+        if table[mask] == 0 && mask & wheel == wheel {
+            table[mask] = 4;
+        }
+        mask += 1;
+    }
+    table
+}
+
+const fn build_top_five_table() -> [RankMask; NUM_RANK_MASKS] {
+    let mut table = [0; NUM_RANK_MASKS];
+    let mut mask = 0;
+    while mask < NUM_RANK_MASKS {
+        let mut top = mask as RankMask;
+        // clear the lowest bit until there're only 5 left
+        while top.count_ones() > 5 {
+            top &= top - 1;
+        }
+        table[mask] = top;
+        mask += 1;
+    }
+    table
+}
+
+/// keep only the `num` highest ranks of the mask
+fn keep_top(mask: RankMask, num: u32) -> RankMask {
+    let mut top = mask;
+    while top.count_ones() > num {
+        top &= top - 1;
+    }
+    top
+}
+
+/// index of the highest rank in the mask
+fn top_index(mask: RankMask) -> u32 {
+    15 - mask.leading_zeros()
+}
+
+/// converts a rank index (0 is deuce, 12 is ace) back to a `Rank`
+fn index_to_rank(index: u32) -> Rank {
+    if index == 12 {
+        1
+    } else {
+        index as Rank + 2
+    }
+}
+
+/// Strength of the best five card hand out of some cards.
+/// A stronger hand always has a greater strength, and hands that tie have the same strength.
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
+pub struct HandStrength(u32);
+impl HandStrength {
+    fn new(category: u32, ranks: u32) -> HandStrength {
+        HandStrength(category << CATEGORY_SHIFT | ranks)
+    }
+    fn get_category(self) -> u32 {
+        self.0 >> CATEGORY_SHIFT
+    }
+    /// the ranks part of the strength, without the category
+    fn get_ranks(self) -> u32 {
+        self.0 & ((1 << CATEGORY_SHIFT) - 1)
+    }
+    pub fn get_hand_type(self) -> HandType {
+        let ranks = self.get_ranks();
+        // rank of the group (pair, trio...) stored above the kickers' mask
+        let group_rank = || index_to_rank(ranks >> 13);
+        match self.get_category() {
+            STRAIGHT_FLUSH if ranks == 12 => HandType::RoyalFlush,
+            STRAIGHT_FLUSH => HandType::StraightFlush(index_to_rank(ranks)),
+            FOUR_OF_A_KIND => HandType::FourOfAKind(group_rank()),
+            FULL_HOUSE => {
+                HandType::FullHouse(index_to_rank(ranks >> 4), index_to_rank(ranks & 0xf))
+            }
+            FLUSH => HandType::Flush,
+            STRAIGHT => HandType::Straight(index_to_rank(ranks)),
+            THREE_OF_A_KIND => HandType::ThreeOfAKind(group_rank()),
+            TWO_PAIR => {
+                let pairs = (ranks >> 13) as RankMask;
+                HandType::TwoPair(
+                    index_to_rank(top_index(pairs)),
+                    index_to_rank(pairs.trailing_zeros()),
+                )
+            }
+            ONE_PAIR => HandType::OnePair(group_rank()),
+            _ => HandType::HighCard(index_to_rank(top_index(ranks as RankMask))),
+        }
+    }
+}
+
+/// evaluates the best hand made by the cards
+/// takes the rank masks of each suit
+fn evaluate_suit_masks(suits: [RankMask; 4]) -> HandStrength {
+    let [s0, s1, s2, s3] = suits;
+
+    // ====flushes====
+    // there can only be one flush suit with 7 cards or less
+    if let Some(&flush) = suits.iter().find(|suit| suit.count_ones() >= 5) {
+        let straight = STRAIGHT_TABLE[flush as usize];
+        if straight != 0 {
+            return HandStrength::new(STRAIGHT_FLUSH, straight as u32 - 1);
+        }
+        return HandStrength::new(FLUSH, TOP_FIVE_TABLE[flush as usize] as u32);
+    }
+
+    let ranks = s0 | s1 | s2 | s3;
+    // ranks that appear at least 2, 3 and 4 times
+    let twos = (s0 & s1) | (s0 & s2) | (s0 & s3) | (s1 & s2) | (s1 & s3) | (s2 & s3);
+    let threes = (s0 & s1 & s2) | (s0 & s1 & s3) | (s0 & s2 & s3) | (s1 & s2 & s3);
+    let fours = s0 & s1 & s2 & s3;
+
+    // ====four of a kind====
+    if fours != 0 {
+        let quad = top_index(fours);
+        let kicker = keep_top(ranks & !(1 << quad), 1);
+        return HandStrength::new(FOUR_OF_A_KIND, quad << 13 | kicker as u32);
+    }
+    // ====full house====
+    if threes != 0 {
+        let trio = top_index(threes);
+        let pairs = twos & !(1 << trio);
+        if pairs != 0 {
+            return HandStrength::new(FULL_HOUSE, trio << 4 | top_index(pairs));
+        }
+    }
+    // ====straight====
+    let straight = STRAIGHT_TABLE[ranks as usize];
+    if straight != 0 {
+        return HandStrength::new(STRAIGHT, straight as u32 - 1);
+    }
+    // ====three of a kind====
+    if threes != 0 {
+        let trio = top_index(threes);
+        let kickers = keep_top(ranks & !(1 << trio), 2);
+        return HandStrength::new(THREE_OF_A_KIND, trio << 13 | kickers as u32);
+    }
+    // ====two pair====
+    if twos.count_ones() >= 2 {
+        let pairs = keep_top(twos, 2);
+        let kicker = keep_top(ranks & !pairs, 1);
+        return HandStrength::new(TWO_PAIR, (pairs as u32) << 13 | kicker as u32);
+    }
+    // ====one pair====
+    if twos != 0 {
+        let pair = top_index(twos);
+        let kickers = keep_top(ranks & !twos, 3);
+        return HandStrength::new(ONE_PAIR, pair << 13 | kickers as u32);
+    }
+    // ====high card====
+    HandStrength::new(HIGH_CARD, TOP_FIVE_TABLE[ranks as usize] as u32)
+}
+
+/// returns the strength of the best five card hand that can be made with `cards`.
+/// takes 2 to 7 cards, with less than 5 cards there can't be straights, flushes or full houses
+pub fn evaluate(cards: CardSet) -> HandStrength {
+    evaluate_suit_masks([0, 1, 2, 3].map(|suit| cards.get_suit_mask(suit)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::{Card, Deck, Hand};
+    use super::*;
+    use itertools::Itertools;
+
+    #[test]
+    fn five_card_category_counts() {
+        // number of distinct five card hands of each category, high card to straight flush
+        let expected = [1302540, 1098240, 123552, 54912, 10200, 5108, 3744, 624, 40];
+        let mut counts = [0; 9];
+        for hand in CardSet::FULL.iter().combinations(5) {
+            counts[evaluate(hand.into_iter().collect()).get_category() as usize] += 1;
+        }
+        assert_eq!(counts, expected);
+    }
+
+    #[test]
+    fn matches_hand_ordering() {
+        for _ in 0..2000 {
+            // two players sharing the same community
+            let mut deck = Deck::new();
+            let community: [Card; 5] = deck.deal(5).try_into().unwrap();
+            let hole1: [Card; 2] = deck.deal(2).try_into().unwrap();
+            let hole2: [Card; 2] = deck.deal(2).try_into().unwrap();
+
+            let best_hand = |hole| {
+                Hand::get_all_hands(hole, community)
+                    .into_iter()
+                    .max()
+                    .unwrap()
+            };
+            let (hand1, hand2) = (best_hand(hole1), best_hand(hole2));
+            let strength1 = evaluate(hole1.iter().chain(&community).collect());
+            let strength2 = evaluate(hole2.iter().chain(&community).collect());
+
+            assert_eq!(strength1.get_hand_type(), hand1.get_hand_type());
+            assert_eq!(
+                strength1.cmp(&strength2),
+                hand1.cmp(&hand2),
+                "{:?} {:?} {:?}",
+                hole1,
+                hole2,
+                community
+            );
+        }
+    }
+}
@@ -0,0 +1,161 @@
+//! The 169 strategically different starting hands, eg. "AA", "AKs", "AKo"
+
+use std::{fmt::Display, str::FromStr};
+
+use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
+
+use super::range::{rank_index_to_char, Kind};
+use super::{Card, ParseRangeError, Range};
+
+/// number of starting hand classes
+pub const NUM_CLASSES: usize = 169;
+
+/// A starting hand without its suits: a pair, or two ranks that are suited or offsuit.
+/// Classes are laid out like the usual 13 by 13 hand chart, aces top left,
+/// pairs on the diagonal, suited hands above it and offsuit hands below it.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub struct HandClass {
+    high: u8, // rank index of the higher card, 0 is deuce and 12 is ace
+    low: u8,  // same as `high` for pairs
+    suited: bool,
+}
+impl HandClass {
+    /// class of the hole cards
+    pub fn from_hole(hole: [Card; 2]) -> HandClass {
+        let [a, b] = hole.map(Card::get_rank_index);
+        HandClass {
+            high: a.max(b),
+            low: a.min(b),
+            suited: hole[0].suit == hole[1].suit,
+        }
+    }
+    /// every class in chart order
+    pub fn all() -> impl Iterator<Item = HandClass> {
+        (0..NUM_CLASSES).map(HandClass::from_index)
+    }
+    /// index of the class in the chart, `row * 13 + column`
+    pub fn get_index(self) -> usize {
+        let (row, column) = self.get_chart_position();
+        row * 13 + column
+    }
+    /// inverse of `get_index`
+    pub fn from_index(index: usize) -> HandClass {
+        assert!(index < NUM_CLASSES, "No hand class at index {}", index);
+        let (row, column) = (index / 13, index % 13);
+        // rows and columns go from aces down to deuces
+        let (rank1, rank2) = (12 - row as u8, 12 - column as u8);
+        HandClass {
+            high: rank1.max(rank2),
+            low: rank1.min(rank2),
+            suited: row < column,
+        }
+    }
+    /// row and column in the hand chart
+    pub fn get_chart_position(self) -> (usize, usize) {
+        let (high, low) = (12 - self.high as usize, 12 - self.low as usize);
+        if self.suited {
+            (high, low)
+        } else {
+            (low, high)
+        }
+    }
+    pub fn is_pair(self) -> bool {
+        self.high == self.low
+    }
+    pub fn is_suited(self) -> bool {
+        self.suited
+    }
+    fn get_kind(self) -> Kind {
+        if self.is_pair() {
+            Kind::Pair
+        } else if self.suited {
+            Kind::Suited
+        } else {
+            Kind::Offsuit
+        }
+    }
+    /// hole cards in the class, 6 for pairs, 4 for suited and 12 for offsuit hands
+    pub fn get_combos(self) -> Vec<[Card; 2]> {
+        Range::class_combos(self.high, self.low, self.get_kind())
+    }
+    /// range with every combo of the class
+    pub fn get_range(self) -> Range {
+        let mut range = Range::new();
+        for combo in self.get_combos() {
+            range.set_weight(combo, 1.0);
+        }
+        range
+    }
+}
+
+impl Display for HandClass {
+    /// eg. "AA", "AKs", "72o"
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let suffix = match self.get_kind() {
+            Kind::Suited => "s",
+            Kind::Offsuit => "o",
+            _ => "",
+        };
+        write!(
+            f,
+            "{}{}{}",
+            rank_index_to_char(self.high),
+            rank_index_to_char(self.low),
+            suffix
+        )
+    }
+}
+impl FromStr for HandClass {
+    type Err = ParseRangeError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (high, low, kind) = Range::parse_class(s)?;
+        match kind {
+            // "AK" is two classes
+            Kind::Any => Err(ParseRangeError::InvalidHand(s.into())),
+            _ => Ok(HandClass {
+                high,
+                low,
+                suited: kind == Kind::Suited,
+            }),
+        }
+    }
+}
+impl Serialize for HandClass {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+impl<'de> Deserialize<'de> for HandClass {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let s = String::deserialize(deserializer)?;
+        s.parse().map_err(de::Error::custom)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn hand_classes() {
+        let mut num_combos = 0;
+        for (index, class) in HandClass::all().enumerate() {
+            assert_eq!(class.get_index(), index);
+            assert_eq!(class.to_string().parse::<HandClass>(), Ok(class));
+            for combo in class.get_combos() {
+                assert_eq!(HandClass::from_hole(combo), class);
+                num_combos += 1;
+            }
+        }
+        assert_eq!(num_combos, 1326);
+
+        let class = HandClass::from_hole(Card::parse_list("Kd Ad").unwrap().try_into().unwrap());
+        assert_eq!(class.to_string(), "AKs");
+        assert_eq!(class.get_chart_position(), (0, 1));
+        assert_eq!(HandClass::from_index(0).to_string(), "AA");
+        assert_eq!(HandClass::from_index(13).to_string(), "AKo");
+        assert_eq!(HandClass::from_index(168).to_string(), "22");
+        assert!("AK".parse::<HandClass>().is_err());
+    }
+}
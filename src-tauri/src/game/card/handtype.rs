@@ -1,12 +1,15 @@
 use std::{cmp::Ordering, fmt::Display};
 
-use super::Card;
-use super::Hand;
+use serde::{Deserialize, Serialize};
+
 use super::Rank;
+use super::{evaluate, Card};
 
 type Ranking = u8;
 
-#[derive(Clone, Copy, Eq, Debug)]
+/// Hand types are equal only if they have the same category and ranks,
+/// eg. `OnePair(2) != OnePair(13)`. Use `cmp_category` to compare only the categories.
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Hash, Debug)]
 pub enum HandType {
     RoyalFlush,
     StraightFlush(Rank),   // Rank is the rank of highest card in the straight
@@ -20,22 +23,23 @@ pub enum HandType {
     HighCard(Rank),      // Rank is the highest card
 }
 impl HandType {
-    pub fn get_hand(hand: Hand) -> HandType {
+    /// returns the type of the hand, `hand` has to be sorted low to high
+    pub fn get_hand(hand: [Card; 5]) -> HandType {
         // cards' suits are the same with the first one
         let is_flush = hand.into_iter().all(|card| card.suit == hand[0].suit);
+        let ranks = hand.map(|card| card.rank);
 
         // ====royal flush====
-        if hand.get_ranks_array() == [10, 11, 12, 13, 1] && is_flush {
+        if ranks == [10, 11, 12, 13, 1] && is_flush {
             return HandType::RoyalFlush;
         }
         // ====straight flush====
         let straight_rank = if hand[4].rank == 1 {
             // if hand has a 1, there're two cases
             // check for 12345 and TJQKA straight
-            let a = hand.get_ranks_array();
-            if a == [2, 3, 4, 5, 1] {
+            if ranks == [2, 3, 4, 5, 1] {
                 Some(5)
-            } else if a == [10, 11, 12, 13, 1] {
+            } else if ranks == [10, 11, 12, 13, 1] {
                 Some(1)
             } else {
                 None
@@ -112,6 +116,16 @@ impl HandType {
         // reached the end, meaning it's not any other type
         HandType::HighCard(hand[4].rank)
     }
+    /// type of the best hand made with 2 to 7 cards, eg. the hole and a partial community.
+    /// with less than 5 cards there can't be straights, flushes or full houses
+    pub fn from_cards(cards: &[Card]) -> HandType {
+        assert!(
+            (2..=7).contains(&cards.len()),
+            "Need 2 to 7 cards to make a hand, got {}",
+            cards.len()
+        );
+        evaluate(cards.iter().collect()).get_hand_type()
+    }
     pub fn get_ranking(self) -> Ranking {
         match self {
             HandType::RoyalFlush => 1,
@@ -126,10 +140,24 @@ impl HandType {
             HandType::HighCard(_) => 10,
         }
     }
-}
-impl PartialEq for HandType {
-    fn eq(&self, other: &Self) -> bool {
-        self.get_ranking() == other.get_ranking()
+    /// ranks that make up the hand type, most important first.
+    /// padded with 0 for hand types with less ranks
+    fn get_ranks(self) -> [Rank; 2] {
+        match self {
+            HandType::RoyalFlush | HandType::Flush => [0, 0],
+            HandType::StraightFlush(r)
+            | HandType::FourOfAKind(r)
+            | HandType::Straight(r)
+            | HandType::ThreeOfAKind(r)
+            | HandType::OnePair(r)
+            | HandType::HighCard(r) => [r, 0],
+            HandType::FullHouse(r1, r2) | HandType::TwoPair(r1, r2) => [r1, r2],
+        }
+    }
+    /// compare only the categories of the hand types, eg. `OnePair(2)` and `OnePair(13)` are equal
+    pub fn cmp_category(&self, other: &Self) -> Ordering {
+        // this hand type is greater if it's ranking is smaller and vice versa
+        self.get_ranking().cmp(&other.get_ranking()).reverse()
     }
 }
 impl PartialOrd for HandType {
@@ -138,9 +166,18 @@ impl PartialOrd for HandType {
     }
 }
 impl Ord for HandType {
+    /// compare the categories, then the ranks that make up the hand type.
+    /// doesn't include kickers, eg. `OnePair(13) > OnePair(2)`
     fn cmp(&self, other: &Self) -> Ordering {
-        // this hand type is greater if it's ranking is smaller and vice versa
-        self.get_ranking().cmp(&other.get_ranking()).reverse()
+        self.cmp_category(other).then_with(|| {
+            let other_ranks = other.get_ranks();
+            self.get_ranks()
+                .iter()
+                .zip(other_ranks.iter())
+                .map(|(&rank, &other_rank)| Card::cmp_rank_values(rank, other_rank))
+                .find(|ordering| ordering.is_ne())
+                .unwrap_or(Ordering::Equal)
+        })
     }
 }
 impl Display for HandType {
@@ -155,7 +192,7 @@ impl Display for HandType {
             }
             HandType::FullHouse(tr, pr) => write!(
                 f,
-                "Straight Flush (trio rank {}; pair rank {})",
+                "Full House (trio rank {}; pair rank {})",
                 Card::display_rank(tr),
                 Card::display_rank(pr)
             ),
@@ -0,0 +1,464 @@
+//! Indexes of hands that are the same for hands that only differ by a permutation of the suits,
+//! eg. As Ks | Qs Js 2h and Ah Kh | Qh Jh 2c have the same index.
+//!
+//! The cards of each suit are split into the rounds they were dealt in,
+//! eg. the hole cards then the flop. The number of cards a suit has in each round is its shape.
+//! Suits are ordered by their shape, and suits with the same shape can be swapped,
+//! so a hand is indexed by its configuration (the sorted shapes of its suits),
+//! then by the multiset of the rank sets of suits with the same shape.
+
+use std::collections::HashMap;
+
+use super::Card;
+
+const NUM_RANKS: u64 = 13;
+
+/// number of ways to choose `k` out of `n`
+fn binomial(n: u64, k: u64) -> u64 {
+    if k > n {
+        return 0;
+    }
+    let k = k.min(n - k);
+    // the result always fits, the intermediate products might not
+    (0..k as u128).fold(1u128, |result, i| result * (n as u128 - i) / (i + 1)) as u64
+}
+
+/// cards of a suit in each round packed 4 bits per round, first round in the highest bits,
+/// so comparing shapes compares the first round first
+type Shape = u16;
+
+fn get_shape_count(shape: Shape, round: usize) -> u64 {
+    (shape >> (4 * (3 - round)) & 0xf) as u64
+}
+
+/// suits with the same shape in a configuration, they can be swapped
+#[derive(Clone, Debug)]
+struct Group {
+    shape: Shape,
+    num_suit: u64,
+    num_rank_sets: u64, // ways a suit with this shape can deal its ranks
+}
+
+/// shapes of the four suits, from the biggest shape
+#[derive(Clone, Debug)]
+struct Configuration {
+    groups: Vec<Group>,
+    offset: u64, // index of the first hand with this configuration
+}
+
+/// Maps hands dealt over rounds to indices, where hands that only differ
+/// by a permutation of the suits get the same index. Indices are dense, from 0 to `get_size`.
+#[derive(Clone, Debug)]
+pub struct HandIndexer {
+    cards_per_round: Vec<usize>,
+    configurations: Vec<Configuration>,
+    // configurations by their 4 shapes packed in a u64
+    configuration_indices: HashMap<u64, usize>,
+    size: u64,
+}
+impl HandIndexer {
+    /// indexer for hands dealt with `cards_per_round` cards in each round, up to 4 rounds.
+    /// eg. `[2, 3]` for the hole cards and the flop, `[3]` for the flop alone
+    pub fn new(cards_per_round: &[usize]) -> HandIndexer {
+        assert!(
+            !cards_per_round.is_empty() && cards_per_round.len() <= 4,
+            "Need 1 to 4 rounds, got {}",
+            cards_per_round.len()
+        );
+        assert!(
+            cards_per_round.iter().sum::<usize>() <= 52,
+            "Can't deal more than 52 cards"
+        );
+        let mut shapes = Vec::new();
+        HandIndexer::enumerate_configurations(
+            &mut cards_per_round.to_vec(),
+            Shape::MAX,
+            &mut Vec::new(),
+            &mut shapes,
+        );
+
+        let mut configurations = Vec::new();
+        let mut configuration_indices = HashMap::new();
+        let mut offset = 0;
+        for suit_shapes in shapes {
+            let mut groups: Vec<Group> = Vec::new();
+            for &shape in &suit_shapes {
+                match groups.last_mut() {
+                    Some(group) if group.shape == shape => group.num_suit += 1,
+                    _ => groups.push(Group {
+                        shape,
+                        num_suit: 1,
+                        num_rank_sets: HandIndexer::count_rank_sets(shape, cards_per_round.len()),
+                    }),
+                }
+            }
+            // multisets of rank sets for each group
+            let size: u64 = groups
+                .iter()
+                .map(|group| binomial(group.num_rank_sets + group.num_suit - 1, group.num_suit))
+                .product();
+            configuration_indices.insert(pack_shapes(&suit_shapes), configurations.len());
+            configurations.push(Configuration { groups, offset });
+            offset += size;
+        }
+        HandIndexer {
+            cards_per_round: cards_per_round.to_vec(),
+            configurations,
+            configuration_indices,
+            size: offset,
+        }
+    }
+    /// hole cards
+    pub fn preflop() -> HandIndexer {
+        HandIndexer::new(&[2])
+    }
+    /// hole cards and the flop
+    pub fn flop() -> HandIndexer {
+        HandIndexer::new(&[2, 3])
+    }
+    /// hole cards, the flop and the turn
+    pub fn turn() -> HandIndexer {
+        HandIndexer::new(&[2, 3, 1])
+    }
+    /// hole cards, the flop, the turn and the river
+    pub fn river() -> HandIndexer {
+        HandIndexer::new(&[2, 3, 1, 1])
+    }
+    /// number of different indices
+    pub fn get_size(&self) -> u64 {
+        self.size
+    }
+    pub fn get_cards_per_round(&self) -> &[usize] {
+        &self.cards_per_round
+    }
+
+    /// add every configuration of suit shapes where each suit's shape is at most the previous
+    fn enumerate_configurations(
+        remaining: &mut Vec<usize>, // cards of each round not given to a suit yet
+        max_shape: Shape,
+        suit_shapes: &mut Vec<Shape>,
+        configurations: &mut Vec<Vec<Shape>>,
+    ) {
+        if suit_shapes.len() == 4 {
+            if remaining.iter().all(|&num_card| num_card == 0) {
+                configurations.push(suit_shapes.clone());
+            }
+            return;
+        }
+        // every shape of the suit, from the biggest
+        let mut shapes = Vec::new();
+        HandIndexer::enumerate_shapes(remaining, 0, 0, 0, &mut shapes);
+        for shape in shapes.into_iter().rev().filter(|&shape| shape <= max_shape) {
+            for (round, num_card) in remaining.iter_mut().enumerate() {
+                *num_card -= get_shape_count(shape, round) as usize;
+            }
+            suit_shapes.push(shape);
+            HandIndexer::enumerate_configurations(remaining, shape, suit_shapes, configurations);
+            suit_shapes.pop();
+            for (round, num_card) in remaining.iter_mut().enumerate() {
+                *num_card += get_shape_count(shape, round) as usize;
+            }
+        }
+    }
+    /// add every shape of a suit that fits in the remaining cards, from the smallest
+    fn enumerate_shapes(
+        remaining: &[usize],
+        round: usize,
+        shape: Shape,
+        num_card: usize,
+        shapes: &mut Vec<Shape>,
+    ) {
+        if round == remaining.len() {
+            shapes.push(shape);
+            return;
+        }
+        for count in 0..=remaining[round].min(NUM_RANKS as usize - num_card) {
+            let shape = shape | (count as Shape) << (4 * (3 - round));
+            HandIndexer::enumerate_shapes(remaining, round + 1, shape, num_card + count, shapes);
+        }
+    }
+    /// ways a suit with the shape can deal its ranks over the rounds
+    fn count_rank_sets(shape: Shape, num_round: usize) -> u64 {
+        let mut available = NUM_RANKS;
+        let mut count = 1;
+        for round in 0..num_round {
+            let num_card = get_shape_count(shape, round);
+            count *= binomial(available, num_card);
+            available -= num_card;
+        }
+        count
+    }
+
+    /// Index of the cards, dealt in the order of the rounds, eg. the hole cards then the flop.
+    /// panics if the number of cards doesn't match the rounds or if a card is repeated
+    pub fn index(&self, cards: &[Card]) -> u64 {
+        assert_eq!(
+            cards.len(),
+            self.cards_per_round.iter().sum::<usize>(),
+            "Wrong number of cards for the rounds"
+        );
+        // rank masks of each suit in each round
+        let mut masks = [[0u16; 4]; 4];
+        let mut start = 0;
+        for (round, &num_card) in self.cards_per_round.iter().enumerate() {
+            for card in &cards[start..start + num_card] {
+                let bit = 1 << card.get_rank_index();
+                let suit_masks = &mut masks[card.suit as usize];
+                assert!(
+                    suit_masks.iter().all(|&mask| mask & bit == 0),
+                    "{} is repeated",
+                    card
+                );
+                suit_masks[round] |= bit;
+            }
+            start += num_card;
+        }
+
+        // shape and index of the rank sets of each suit, sorted from the biggest
+        let mut suits: Vec<(Shape, u64)> = masks
+            .iter()
+            .map(|suit_masks| self.index_suit(suit_masks))
+            .collect();
+        suits.sort_unstable_by(|a, b| b.cmp(a));
+        let shapes: Vec<Shape> = suits.iter().map(|&(shape, _)| shape).collect();
+        let configuration = &self.configurations[self.configuration_indices[&pack_shapes(&shapes)]];
+
+        // mixed radix of the multiset index of each group
+        let mut index = 0;
+        let mut suits = suits.iter().map(|&(_, suit_index)| suit_index);
+        for group in &configuration.groups {
+            let num_multiset = binomial(group.num_rank_sets + group.num_suit - 1, group.num_suit);
+            let group_indices: Vec<u64> = suits.by_ref().take(group.num_suit as usize).collect();
+            index = index * num_multiset + rank_multiset(&group_indices);
+        }
+        configuration.offset + index
+    }
+    /// index of the hole cards and the board, the board has the cards of the other rounds
+    pub fn index_hand(&self, hole: &[Card], board: &[Card]) -> u64 {
+        self.index(&[hole, board].concat())
+    }
+    /// shape of the suit and the index of its rank sets.
+    /// each round chooses its ranks out of those not dealt in the previous rounds
+    fn index_suit(&self, suit_masks: &[u16; 4]) -> (Shape, u64) {
+        let mut shape = 0;
+        let mut index = 0;
+        let mut multiplier = 1;
+        let mut used = 0u16;
+        let mut available = NUM_RANKS;
+        for (round, &mask) in suit_masks[..self.cards_per_round.len()].iter().enumerate() {
+            let num_card = mask.count_ones() as u64;
+            shape |= (num_card as Shape) << (4 * (3 - round));
+            // colex rank of the set with the used ranks removed
+            let mut set_index = 0;
+            let mut remaining = mask;
+            let mut position = 1;
+            while remaining != 0 {
+                let bit = remaining & remaining.wrapping_neg();
+                let rank_position = (!used & (bit - 1)).count_ones() as u64;
+                set_index += binomial(rank_position, position);
+                position += 1;
+                remaining &= remaining - 1;
+            }
+            index += set_index * multiplier;
+            multiplier *= binomial(available, num_card);
+            available -= num_card;
+            used |= mask;
+        }
+        (shape, index)
+    }
+
+    /// Canonical cards of the index, in the order of the rounds.
+    /// `index(unindex(i)) == i`, and hands with the same index all unindex to the same cards
+    pub fn unindex(&self, index: u64) -> Vec<Card> {
+        assert!(index < self.size, "Index {} out of range", index);
+        // last configuration that starts at or before the index
+        let configuration_index = self
+            .configurations
+            .partition_point(|configuration| configuration.offset <= index)
+            - 1;
+        let configuration = &self.configurations[configuration_index];
+
+        // undo the mixed radix, from the last group
+        let mut remaining = index - configuration.offset;
+        let mut suits: Vec<(Shape, u64)> = Vec::new();
+        for group in configuration.groups.iter().rev() {
+            let num_multiset = binomial(group.num_rank_sets + group.num_suit - 1, group.num_suit);
+            let group_indices = unrank_multiset(remaining % num_multiset, group.num_suit);
+            remaining /= num_multiset;
+            // groups are added in reverse, so the suits are too
+            suits.extend(
+                group_indices
+                    .into_iter()
+                    .rev()
+                    .map(|suit_index| (group.shape, suit_index)),
+            );
+        }
+        suits.reverse();
+
+        let mut rounds: Vec<Vec<Card>> = vec![Vec::new(); self.cards_per_round.len()];
+        for (suit, &(shape, suit_index)) in suits.iter().enumerate() {
+            let masks = self.unindex_suit(shape, suit_index);
+            for (round, &mask) in masks.iter().enumerate() {
+                let mut mask = mask;
+                while mask != 0 {
+                    let rank_index = mask.trailing_zeros() as u8;
+                    rounds[round].push(Card::from_index(suit as u8 * 13 + rank_index));
+                    mask &= mask - 1;
+                }
+            }
+        }
+        rounds.concat()
+    }
+    /// rank masks of each round of a suit, inverse of `index_suit`
+    fn unindex_suit(&self, shape: Shape, suit_index: u64) -> Vec<u16> {
+        let mut masks = Vec::new();
+        let mut remaining = suit_index;
+        let mut used = 0u16;
+        let mut available = NUM_RANKS;
+        for round in 0..self.cards_per_round.len() {
+            let num_card = get_shape_count(shape, round);
+            let num_set = binomial(available, num_card);
+            let mut set_index = remaining % num_set;
+            remaining /= num_set;
+            // positions among the unused ranks, from the highest
+            let mut mask = 0;
+            for position in (1..=num_card).rev() {
+                let mut rank_position = position - 1;
+                while binomial(rank_position + 1, position) <= set_index {
+                    rank_position += 1;
+                }
+                set_index -= binomial(rank_position, position);
+                mask |= nth_unused_rank(used, rank_position);
+            }
+            masks.push(mask);
+            used |= mask;
+            available -= num_card;
+        }
+        masks
+    }
+
+    /// canonical cards of every index, in the order of the indices
+    pub fn canonical_hands(&self) -> impl Iterator<Item = Vec<Card>> + '_ {
+        (0..self.size).map(move |index| self.unindex(index))
+    }
+}
+
+/// the 4 shapes of a configuration as one key
+fn pack_shapes(shapes: &[Shape]) -> u64 {
+    shapes
+        .iter()
+        .fold(0, |key, &shape| key << 16 | shape as u64)
+}
+
+/// bit of the `n`th (from 0) rank that isn't in `used`
+fn nth_unused_rank(used: u16, n: u64) -> u16 {
+    let mut unused = !used & 0x1fff;
+    for _ in 0..n {
+        unused &= unused - 1;
+    }
+    unused & unused.wrapping_neg()
+}
+
+/// index of a multiset given from the biggest element, among the multisets of the same size
+fn rank_multiset(elements: &[u64]) -> u64 {
+    let size = elements.len() as u64;
+    // adding the distance to the end makes the elements strictly decreasing
+    elements
+        .iter()
+        .enumerate()
+        .map(|(i, &element)| {
+            let remaining = size - i as u64;
+            binomial(element + remaining - 1, remaining)
+        })
+        .sum()
+}
+
+/// inverse of `rank_multiset`, the elements from the biggest
+fn unrank_multiset(index: u64, size: u64) -> Vec<u64> {
+    let mut index = index;
+    let mut elements = Vec::new();
+    for remaining in (1..=size).rev() {
+        // biggest strictly decreasing element that fits, found by doubling then bisecting
+        let mut low = remaining - 1;
+        let mut high = low + 1;
+        while binomial(high, remaining) <= index {
+            low = high;
+            high *= 2;
+        }
+        while high - low > 1 {
+            let middle = (low + high) / 2;
+            if binomial(middle, remaining) <= index {
+                low = middle;
+            } else {
+                high = middle;
+            }
+        }
+        index -= binomial(low, remaining);
+        elements.push(low + 1 - remaining);
+    }
+    elements
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::{CardSet, Suit};
+    use super::*;
+    use rand::{rngs::StdRng, seq::SliceRandom, Rng, SeedableRng};
+
+    #[test]
+    fn sizes() {
+        assert_eq!(HandIndexer::preflop().get_size(), 169);
+        assert_eq!(HandIndexer::new(&[3]).get_size(), 1755);
+        assert_eq!(HandIndexer::flop().get_size(), 1_286_792);
+        assert_eq!(HandIndexer::turn().get_size(), 55_190_538);
+        assert_eq!(HandIndexer::river().get_size(), 2_428_287_420);
+        // the whole board at once, the order of the streets doesn't matter
+        assert_eq!(HandIndexer::new(&[2, 5]).get_size(), 123_156_254);
+    }
+
+    #[test]
+    fn index_and_unindex() {
+        for indexer in [HandIndexer::preflop(), HandIndexer::new(&[3])] {
+            for (index, cards) in indexer.canonical_hands().enumerate() {
+                assert_eq!(indexer.index(&cards), index as u64);
+            }
+        }
+
+        let mut rng = StdRng::seed_from_u64(0);
+        for indexer in [HandIndexer::flop(), HandIndexer::river()] {
+            let num_card = indexer.get_cards_per_round().iter().sum();
+            for _ in 0..2000 {
+                let index = rng.gen_range(0..indexer.get_size());
+                assert_eq!(indexer.index(&indexer.unindex(index)), index);
+
+                // swapping suits keeps the index
+                let mut cards: Vec<Card> =
+                    CardSet::draw_excluding(CardSet::EMPTY, num_card, &mut rng)
+                        .iter()
+                        .collect();
+                cards.shuffle(&mut rng);
+                let mut permutation = [0, 1, 2, 3];
+                permutation.shuffle(&mut rng);
+                let swapped: Vec<Card> = cards
+                    .iter()
+                    .map(|card| {
+                        Card::new(card.rank, Suit::ALL_SUITS[permutation[card.suit as usize]])
+                    })
+                    .collect();
+                assert_eq!(indexer.index(&cards), indexer.index(&swapped));
+            }
+        }
+
+        // same flop with the suits swapped
+        let indexer = HandIndexer::flop();
+        let cards = |s: &str| Card::parse_list(s).unwrap();
+        assert_eq!(
+            indexer.index(&cards("As Ks Qs Js 2h")),
+            indexer.index_hand(&cards("Ah Kh"), &cards("Qh Jh 2c"))
+        );
+        assert_ne!(
+            indexer.index(&cards("As Ks Qs Js 2h")),
+            indexer.index(&cards("As Ks Qh Jh 2h"))
+        );
+    }
+}
@@ -1,15 +1,29 @@
-use std::{array::IntoIter, cmp::Ordering, collections::LinkedList, fmt::Display, ops::Index};
+use std::{cmp::Ordering, fmt::Display, ops::Index};
 
+mod best_hand;
+mod cardset;
+mod evaluator;
+mod hand_class;
 mod handtype;
+mod isomorphism;
+mod notation;
+mod range;
 
 use concat_arrays::concat_arrays;
 use itertools::Itertools;
-use rand::seq::SliceRandom;
+use rand::{rngs::StdRng, seq::SliceRandom, SeedableRng};
 use serde::{Deserialize, Serialize};
 
-use self::handtype::HandType;
+pub use self::best_hand::BestHand;
+pub use self::cardset::CardSet;
+pub use self::evaluator::{evaluate, HandStrength};
+pub use self::hand_class::{HandClass, NUM_CLASSES};
+pub use self::handtype::HandType;
+pub use self::isomorphism::HandIndexer;
+pub use self::notation::{short, short_list, ParseCardError};
+pub use self::range::{ParseRangeError, Range, NUM_COMBOS};
 
-#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
 enum Suit {
     Spade,
     Club,
@@ -32,7 +46,9 @@ impl Display for Suit {
 
 type Rank = u8;
 
-#[derive(Debug, Serialize, Deserialize, Clone, Copy, Eq)]
+/// Two cards are equal only if they have the same rank and suit.
+/// Use `cmp_rank` to compare only the ranks.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Hash)]
 pub struct Card {
     rank: Rank, // J, Q, K, A are 11, 12, 13, 1 respectively
     suit: Suit,
@@ -44,20 +60,65 @@ impl Card {
         }
         Card { rank, suit }
     }
+    /// index of the rank from lowest to highest, 0 is deuce and 12 is ace
+    fn get_rank_index(self) -> u8 {
+        if self.rank == 1 {
+            12
+        } else {
+            self.rank - 2
+        }
+    }
+    /// index of the card in a `CardSet`, suit major
+    fn get_index(self) -> u8 {
+        self.suit as u8 * 13 + self.get_rank_index()
+    }
+    /// inverse of `get_index`
+    fn from_index(index: u8) -> Card {
+        let rank_index = index % 13;
+        let rank = if rank_index == 12 { 1 } else { rank_index + 2 };
+        Card::new(rank, Suit::ALL_SUITS[index as usize / 13])
+    }
+    /// compare the ranks of the cards, ignoring suits. aces are highest
+    pub fn cmp_rank(&self, other: &Card) -> Ordering {
+        Card::cmp_rank_values(self.rank, other.rank)
+    }
+    /// compare ranks, aces are highest
+    fn cmp_rank_values(rank: Rank, other_rank: Rank) -> Ordering {
+        if rank == 1 {
+            if other_rank == 1 {
+                return Ordering::Equal;
+            }
+            return Ordering::Greater;
+        }
+        if other_rank == 1 {
+            return Ordering::Less;
+        }
+        rank.cmp(&other_rank)
+    }
     fn display_rank(rank: Rank) -> String {
         match rank {
-            1 => "Ace".into(),
-            11 => "Jack".into(),
-            12 => "Queen".into(),
-            13 => "King".into(),
-            _ => rank.to_string(),
+            1 => "Ace",
+            2 => "Two",
+            3 => "Three",
+            4 => "Four",
+            5 => "Five",
+            6 => "Six",
+            7 => "Seven",
+            8 => "Eight",
+            9 => "Nine",
+            10 => "Ten",
+            11 => "Jack",
+            12 => "Queen",
+            _ => "King",
         }
+        .into()
     }
-}
-impl PartialEq for Card {
-    /// two cards are equal if they have the same rank
-    fn eq(&self, other: &Self) -> bool {
-        self.rank == other.rank
+    /// eg. "Kings", "Sixes"
+    fn display_rank_plural(rank: Rank) -> String {
+        match rank {
+            6 => "Sixes".into(),
+            _ => format!("{}s", Card::display_rank(rank)),
+        }
     }
 }
 impl PartialOrd for Card {
@@ -66,69 +127,31 @@ impl PartialOrd for Card {
     }
 }
 impl Ord for Card {
-    /// compare cards.
-    /// the suit does not matter, eg. two of spade == two of clubs.
-    /// aces are highest.
+    /// compare cards by rank then suit, aces are highest.
+    /// the suit only breaks ties so cards of different suits aren't equal
     fn cmp(&self, other: &Self) -> Ordering {
-        if self.rank == 1 {
-            if other.rank == 1 {
-                return Ordering::Equal;
-            }
-            return Ordering::Greater;
-        }
-        if other.rank == 1 {
-            return Ordering::Less;
-        }
-        self.rank.cmp(&other.rank)
-    }
-}
-impl Display for Card {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "{} of {}", Card::display_rank(self.rank), self.suit)
+        self.cmp_rank(other).then(self.suit.cmp(&other.suit))
     }
 }
-
 /// Wrapper for type [Card; 5], makes sure that hand is always sorted.
-#[derive(Clone, Copy, Eq)]
+/// Hands are equal if they have the same strength, regardless of suits.
+#[derive(Clone, Copy, Eq, Debug)]
 pub struct Hand {
     cards: [Card; 5],
-
-    // hand_type is initially None.
-    // is Some when `get_hand_type` is first called
-    // acts as a cache so we don't have to check every hand type
-    // when we want to get hand type
-    hand_type: Option<HandType>,
+    hand_type: HandType, // calculated once so comparisons don't have to
 }
 impl Hand {
     fn new(cards: [Card; 5]) -> Hand {
-        let mut sorted = cards.clone();
+        let mut sorted = cards;
         // sort low to high
         sorted.sort();
         Hand {
             cards: sorted,
-            hand_type: None,
+            hand_type: HandType::get_hand(sorted),
         }
     }
-    fn into_iter(self) -> IntoIter<Card, 5> {
-        self.cards.into_iter()
-    }
-    fn get_ranks_array(self) -> [Rank; 5] {
-        self.cards
-            .iter()
-            .map(|card| card.rank)
-            .collect::<Vec<Rank>>()
-            .try_into()
-            .unwrap()
-    }
-    /// returns the hand's type.
-    /// O(1) if previously calculated.
     pub fn get_hand_type(self) -> HandType {
-        // check if already calculated
-        if let Some(hand_type) = self.hand_type {
-            return hand_type;
-        }
-
-        HandType::get_hand(self)
+        self.hand_type
     }
     /// returns all possible hand that can be made using current hole and community.
     pub fn get_all_hands(hole: [Card; 2], community: [Card; 5]) -> Vec<Hand> {
@@ -141,6 +164,25 @@ impl Hand {
             .map(|possible_hand| Hand::new(possible_hand.try_into().unwrap()))
             .collect()
     }
+    /// returns the cards ordered by how they break ties, see `tie_break_order`
+    fn get_tie_break_order(self) -> [Card; 5] {
+        tie_break_order(&self.cards).try_into().unwrap()
+    }
+}
+
+/// returns the cards ordered by how they break ties:
+/// bigger groups of the same rank first, then higher ranks first.
+/// eg. 3-3-K-K-K is ordered K-K-K-3-3, A-Q-9-9-5 is ordered 9-9-A-Q-5
+fn tie_break_order(cards: &[Card]) -> Vec<Card> {
+    let count = |card: &Card| cards.iter().filter(|other| other.rank == card.rank).count();
+    let mut ordered = cards.to_vec();
+    ordered.sort_by(|a, b| {
+        count(b)
+            .cmp(&count(a))
+            .then(b.cmp_rank(a))
+            .then(a.suit.cmp(&b.suit))
+    });
+    ordered
 }
 impl Index<usize> for Hand {
     type Output = Card;
@@ -161,150 +203,81 @@ impl PartialOrd for Hand {
 }
 impl Ord for Hand {
     fn cmp(&self, other: &Self) -> Ordering {
-        let self_hand_type = self.get_hand_type();
-        let other_hand_type = other.get_hand_type();
-        let hand_type_cmp = self_hand_type.cmp(&other_hand_type);
-        if hand_type_cmp.is_ne() {
-            return hand_type_cmp; // return if there're no ties
-        }
-        // breaking tie, self_hand_type should be the same as other_hand_type
-
-        /// helper function to compare the ranks of hands. takes in vec because
-        /// sometimes we need to compare sections of hands.
-        /// `h1` and `h2` has to be the same size and sorted low to high
-        fn compare_ranks(h1: Vec<Card>, h2: Vec<Card>) -> Ordering {
-            // going backwards from highest to lowest card of both hands
-            for (c1, c2) in h1.iter().zip(h2).rev() {
-                let compare_result = c1.cmp(&c2);
-                if compare_result.is_ne() {
-                    return compare_result;
-                }
-            }
-            // all elements are equal
-            Ordering::Equal
-        }
-        // closure that compare hands if there're no specific tie breaking rules
-        let default_tie_break = || -> Ordering {
-            return compare_ranks(self.cards.to_vec(), other.cards.to_vec());
-        };
-
-        fn straight_tie_break(self_straight_rank: Rank, other_straight_rank: Rank) -> Ordering {
-            if self_straight_rank == 1 {
-                if other_straight_rank == 1 {
-                    return Ordering::Equal;
-                }
-                return Ordering::Greater;
-            }
-            if other_straight_rank == 1 {
-                return Ordering::Less;
-            }
-            return self_straight_rank.cmp(&other_straight_rank);
-        }
-        let x_of_a_kind_tie_break = |self_rank: Rank, other_rank: Rank| -> Ordering {
-            let compare_result = self_rank.cmp(&other_rank);
-            if compare_result.is_ne() {
-                return compare_result;
-            }
-            return compare_ranks(
-                self.cards
-                    .into_iter()
-                    .filter(|card| card.rank != self_rank)
-                    .collect(),
-                other
-                    .cards
-                    .into_iter()
-                    .filter(|card| card.rank != other_rank)
-                    .collect(),
-            );
-        };
-        fn full_house_tie_break(str: Rank, spr: Rank, otr: Rank, opr: Rank) -> Ordering {
-            let compare_result = str.cmp(&otr);
-            if compare_result.is_ne() {
-                return compare_result;
-            }
-            spr.cmp(&opr)
-        }
-        let two_pair_tie_break = |spr1: Rank, spr2: Rank, opr1: Rank, opr2: Rank| -> Ordering {
-            let cmp_res_1 = spr1.cmp(&opr1);
-            if cmp_res_1.is_ne() {
-                return cmp_res_1;
-            }
-            let cmp_res_2 = spr2.cmp(&opr2);
-            if cmp_res_2.is_ne() {
-                return cmp_res_2;
-            }
-            // remove the two pair and compare ranks if both pair are the same
-            return compare_ranks(
-                self.cards
-                    .into_iter()
-                    .filter(|card| card.rank != spr1 && card.rank != spr2)
-                    .collect(), // should have one card left
-                other
-                    .cards
-                    .into_iter()
-                    .filter(|card| card.rank != opr1 && card.rank != opr2)
-                    .collect(), // should have one card left
-            );
-        };
-
-        match (self_hand_type, other_hand_type) {
-            (HandType::RoyalFlush, HandType::RoyalFlush) => Ordering::Equal, // royal flushes are the same
-            (HandType::StraightFlush(ssr), HandType::StraightFlush(osr)) => {
-                straight_tie_break(ssr, osr)
-            }
-            (HandType::FourOfAKind(sfoakr), HandType::FourOfAKind(ofoakr)) => {
-                x_of_a_kind_tie_break(sfoakr, ofoakr)
-            }
-            (HandType::FullHouse(str, spr), HandType::FullHouse(otr, opr)) => {
-                full_house_tie_break(str, spr, otr, opr)
-            }
-            (HandType::Flush, HandType::Flush) => default_tie_break(),
-            (HandType::Straight(ssr), HandType::Straight(osr)) => straight_tie_break(ssr, osr),
-            (HandType::ThreeOfAKind(stoakr), HandType::ThreeOfAKind(otoakr)) => {
-                x_of_a_kind_tie_break(stoakr, otoakr)
-            }
-            (HandType::TwoPair(spr1, spr2), HandType::TwoPair(opr1, opr2)) => {
-                two_pair_tie_break(spr1, spr2, opr1, opr2)
-            }
-            (HandType::OnePair(spr), HandType::OnePair(opr)) => x_of_a_kind_tie_break(spr, opr),
-            (HandType::HighCard(_), HandType::HighCard(_)) => default_tie_break(),
-            _ => panic!(
-                "Hands are not the same type so can't tie break. 
-                \nSelf hand type: {:?}.
-                \nOther hand type:{:?}",
-                self_hand_type, other_hand_type
-            ),
-        }
+        // compare the hand types, which includes the ranks that made them
+        self.hand_type.cmp(&other.hand_type).then_with(|| {
+            // break ties with the kickers, or all the cards for flushes and high cards.
+            // the hand types are the same so the groups line up
+            self.get_tie_break_order()
+                .iter()
+                .zip(other.get_tie_break_order().iter())
+                .map(|(card, other_card)| card.cmp_rank(other_card))
+                .find(|ordering| ordering.is_ne())
+                .unwrap_or(Ordering::Equal)
+        })
     }
 }
 
 #[derive(Serialize, Deserialize, Clone)]
 pub struct Deck {
-    cards: LinkedList<u8>, // u8 represent card's index in a sorted deck
+    cards: CardSet, // cards that haven't been dealt
+    // cards in the order they're dealt from the end, random if empty.
+    // not serialized, a game sent to the frontend would give the next cards away
+    #[serde(skip)]
+    stacked: Vec<Card>,
 }
 impl Deck {
     pub fn new() -> Deck {
-        // create a new shuffled deck
-        let mut cards: Vec<u8> = (0..52).collect();
-        cards.shuffle(&mut rand::thread_rng());
         Deck {
-            cards: cards.into_iter().collect(),
+            cards: CardSet::FULL,
+            stacked: Vec::new(),
         }
     }
+    /// deck dealt in an order shuffled with `seed`
+    pub fn shuffled(seed: u64) -> Deck {
+        let mut stacked: Vec<Card> = CardSet::FULL.iter().collect();
+        stacked.shuffle(&mut StdRng::seed_from_u64(seed));
+        Deck {
+            cards: CardSet::FULL,
+            stacked,
+        }
+    }
+    /// deck without the `dead` cards
+    pub fn without(dead: CardSet) -> Deck {
+        Deck {
+            cards: !dead,
+            stacked: Vec::new(),
+        }
+    }
+    pub fn get_remaining(&self) -> CardSet {
+        self.cards
+    }
     pub fn random_card(&mut self) -> Card {
-        // get the top card of the shuffled deck
-        let index = self.cards.pop_front().expect("Deck is empty!");
+        if let Some(card) = self.stacked.pop() {
+            self.cards.remove(card);
+            return card;
+        }
+        // draw a random card out of the remaining ones
+        self.cards
+            .draw(&mut rand::thread_rng())
+            .expect("Deck is empty!")
+    }
+    /// deal `num_card` cards from the top of the deck
+    pub fn deal(&mut self, num_card: usize) -> Vec<Card> {
+        (0..num_card).map(|_| self.random_card()).collect()
+    }
+}
 
-        // calculate rank and suit based on index
-        Card::new(index / 4 + 1, Suit::ALL_SUITS[index as usize % 4])
+impl Default for Deck {
+    fn default() -> Self {
+        Deck::new()
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use rand;
     use rand::seq::SliceRandom;
+    use std::collections::HashSet;
 
     fn create_hand(ranks: [Rank; 5], suits: [Suit; 5]) -> Hand {
         Hand::new(
@@ -361,4 +334,38 @@ mod tests {
         assert_eq!(royal_flush.cmp(&foak), Ordering::Greater);
         assert_eq!(foak.cmp(&full_house), Ordering::Greater);
     }
+
+    #[test]
+    fn equality_tests() {
+        let ace_of_spades = Card::new(1, Suit::Spade);
+        let ace_of_hearts = Card::new(1, Suit::Heart);
+        assert_ne!(ace_of_spades, ace_of_hearts);
+        assert!(ace_of_spades.cmp_rank(&ace_of_hearts).is_eq());
+        let unique_cards: HashSet<Card> = [ace_of_spades, ace_of_hearts, ace_of_spades].into();
+        assert_eq!(unique_cards.len(), 2);
+
+        assert_ne!(HandType::OnePair(2), HandType::OnePair(13));
+        assert!(HandType::OnePair(2)
+            .cmp_category(&HandType::OnePair(13))
+            .is_eq());
+        assert!(HandType::OnePair(1) > HandType::OnePair(13));
+        assert!(HandType::TwoPair(13, 2) > HandType::TwoPair(12, 11));
+    }
+
+    #[test]
+    fn shuffled_deck() {
+        // the same seed deals the same cards
+        let mut deck = Deck::shuffled(7);
+        let dealt = deck.deal(52);
+        assert_eq!(dealt, Deck::shuffled(7).deal(52));
+        assert_ne!(dealt, Deck::shuffled(8).deal(52));
+        assert_eq!(dealt.iter().collect::<CardSet>(), CardSet::FULL);
+        assert!(deck.get_remaining().is_empty());
+
+        // the order isn't serialized, a deserialized deck deals randomly
+        let json = serde_json::to_string(&Deck::shuffled(7)).unwrap();
+        let deck: Deck = serde_json::from_str(&json).unwrap();
+        assert!(deck.stacked.is_empty());
+        assert_eq!(deck.get_remaining(), CardSet::FULL);
+    }
 }
@@ -0,0 +1,222 @@
+//! Short card notation, a rank character followed by a suit character, eg. "As", "Td", "7c"
+
+use std::{error::Error, fmt::Display, str::FromStr};
+
+use super::{Card, CardSet, Rank, Suit};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ParseCardError {
+    InvalidRank(char),
+    InvalidSuit(char),
+    MissingSuit(char),      // rank without a suit at the end of the string
+    ExpectedOneCard(usize), // number of cards found when parsing a single card
+    DuplicateCard(Card),
+}
+impl Display for ParseCardError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ParseCardError::InvalidRank(c) => write!(f, "'{}' is not a valid rank", c),
+            ParseCardError::InvalidSuit(c) => write!(f, "'{}' is not a valid suit", c),
+            ParseCardError::MissingSuit(c) => write!(f, "Rank '{}' is missing a suit", c),
+            ParseCardError::ExpectedOneCard(num_card) => {
+                write!(f, "Expected one card, found {}", num_card)
+            }
+            ParseCardError::DuplicateCard(card) => write!(f, "{} appears more than once", card),
+        }
+    }
+}
+impl Error for ParseCardError {}
+
+impl Suit {
+    fn to_char(self) -> char {
+        match self {
+            Suit::Spade => 's',
+            Suit::Club => 'c',
+            Suit::Diamond => 'd',
+            Suit::Heart => 'h',
+        }
+    }
+    fn from_char(c: char) -> Result<Suit, ParseCardError> {
+        match c {
+            's' | 'S' => Ok(Suit::Spade),
+            'c' | 'C' => Ok(Suit::Club),
+            'd' | 'D' => Ok(Suit::Diamond),
+            'h' | 'H' => Ok(Suit::Heart),
+            _ => Err(ParseCardError::InvalidSuit(c)),
+        }
+    }
+}
+
+impl Card {
+    pub(super) fn rank_to_char(rank: Rank) -> char {
+        match rank {
+            1 => 'A',
+            10 => 'T',
+            11 => 'J',
+            12 => 'Q',
+            13 => 'K',
+            _ => (b'0' + rank) as char,
+        }
+    }
+    pub(super) fn rank_from_char(c: char) -> Result<Rank, ParseCardError> {
+        match c.to_ascii_uppercase() {
+            'A' => Ok(1),
+            'T' => Ok(10),
+            'J' => Ok(11),
+            'Q' => Ok(12),
+            'K' => Ok(13),
+            '2'..='9' => Ok(c as u8 - b'0'),
+            _ => Err(ParseCardError::InvalidRank(c)),
+        }
+    }
+    /// the long name of the card, eg. "Ace of Spades"
+    pub fn get_name(self) -> String {
+        format!("{} of {}", Card::display_rank(self.rank), self.suit)
+    }
+    /// parse cards written one after another, with or without whitespace in between.
+    /// eg. "AsKd", "Ts 9h 2c". keeps the order of the cards
+    pub fn parse_list(s: &str) -> Result<Vec<Card>, ParseCardError> {
+        let mut chars = s.chars().filter(|c| !c.is_whitespace());
+        let mut cards = Vec::new();
+        let mut seen = CardSet::EMPTY;
+        while let Some(rank) = chars.next() {
+            let suit = chars.next().ok_or(ParseCardError::MissingSuit(rank))?;
+            let card = Card::new(Card::rank_from_char(rank)?, Suit::from_char(suit)?);
+            if !seen.insert(card) {
+                return Err(ParseCardError::DuplicateCard(card));
+            }
+            cards.push(card);
+        }
+        Ok(cards)
+    }
+    /// write cards in short notation, separated by spaces
+    pub fn format_list(cards: &[Card]) -> String {
+        cards
+            .iter()
+            .map(|card| card.to_string())
+            .collect::<Vec<String>>()
+            .join(" ")
+    }
+}
+impl Display for Card {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}{}",
+            Card::rank_to_char(self.rank),
+            self.suit.to_char()
+        )
+    }
+}
+impl FromStr for Card {
+    type Err = ParseCardError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match Card::parse_list(s)?[..] {
+            [card] => Ok(card),
+            ref cards => Err(ParseCardError::ExpectedOneCard(cards.len())),
+        }
+    }
+}
+
+impl Display for CardSet {
+    /// cards from highest to lowest rank, eg. "AsKdKc2h"
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut cards: Vec<Card> = self.iter().collect();
+        cards.sort_by(|a, b| b.cmp(a));
+        for card in cards {
+            write!(f, "{}", card)?;
+        }
+        Ok(())
+    }
+}
+impl FromStr for CardSet {
+    type Err = ParseCardError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(Card::parse_list(s)?.into_iter().collect())
+    }
+}
+
+/// serde representation of a card in short notation.
+/// use with `#[serde(with = "short")]`
+pub mod short {
+    use serde::{de::Error, Deserialize, Deserializer, Serializer};
+
+    use super::Card;
+
+    pub fn serialize<S: Serializer>(card: &Card, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(card)
+    }
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Card, D::Error> {
+        let s = String::deserialize(deserializer)?;
+        s.parse().map_err(D::Error::custom)
+    }
+}
+
+/// serde representation of a list of cards as one string in short notation, eg. "As Kd".
+/// use with `#[serde(with = "short_list")]`
+pub mod short_list {
+    use serde::{de::Error, Deserialize, Deserializer, Serializer};
+
+    use super::Card;
+
+    pub fn serialize<S: Serializer>(cards: &[Card], serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&Card::format_list(cards))
+    }
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<Card>, D::Error> {
+        let s = String::deserialize(deserializer)?;
+        Card::parse_list(&s).map_err(D::Error::custom)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde::{Deserialize, Serialize};
+
+    use super::*;
+
+    #[test]
+    fn parse_and_display() {
+        let ace_of_spades: Card = "As".parse().unwrap();
+        assert_eq!((ace_of_spades.rank, ace_of_spades.suit), (1, Suit::Spade));
+        assert_eq!(ace_of_spades.to_string(), "As");
+        assert_eq!(ace_of_spades.get_name(), "Ace of Spades");
+
+        let cards = Card::parse_list("Ts 9h2c").unwrap();
+        assert_eq!(Card::format_list(&cards), "Ts 9h 2c");
+        let set: CardSet = "2hAsKd".parse().unwrap();
+        assert_eq!(set.to_string(), "AsKd2h");
+
+        assert_eq!("1s".parse::<Card>(), Err(ParseCardError::InvalidRank('1')));
+        assert_eq!("Ax".parse::<Card>(), Err(ParseCardError::InvalidSuit('x')));
+        assert_eq!(
+            "AsK".parse::<CardSet>(),
+            Err(ParseCardError::MissingSuit('K'))
+        );
+        assert_eq!("".parse::<Card>(), Err(ParseCardError::ExpectedOneCard(0)));
+        assert_eq!(
+            "AsKd".parse::<Card>(),
+            Err(ParseCardError::ExpectedOneCard(2))
+        );
+        assert!(matches!(
+            "As As".parse::<CardSet>(),
+            Err(ParseCardError::DuplicateCard(_))
+        ));
+    }
+
+    #[test]
+    fn short_serde() {
+        #[derive(Serialize, Deserialize)]
+        struct Fixture {
+            #[serde(with = "short")]
+            card: Card,
+            #[serde(with = "short_list")]
+            board: Vec<Card>,
+        }
+        let json = r#"{"card":"Td","board":"As Kd 7c"}"#;
+        let fixture: Fixture = serde_json::from_str(json).unwrap();
+        assert_eq!(fixture.card.to_string(), "Td");
+        assert_eq!(serde_json::to_string(&fixture).unwrap(), json);
+    }
+}
@@ -0,0 +1,424 @@
+//! Weighted ranges of hole cards in the usual notation, eg. "QQ+, AKs, A5s-A2s, KQo, 76s:0.5"
+
+use std::{error::Error, fmt::Display, str::FromStr};
+
+use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
+
+use super::{Card, CardSet, ParseCardError, Suit};
+
+/// number of different two card hands
+pub const NUM_COMBOS: usize = 1326;
+
+/// the card indices of each combo, lowest first.
+/// combo `[low, high]` is at index `high * (high - 1) / 2 + low`
+static COMBOS: [[u8; 2]; NUM_COMBOS] = build_combos();
+
+const fn build_combos() -> [[u8; 2]; NUM_COMBOS] {
+    let mut combos = [[0; 2]; NUM_COMBOS];
+    let mut index = 0;
+    let mut high = 1;
+    while high < 52 {
+        let mut low = 0;
+        while low < high {
+            combos[index] = [low as u8, high as u8];
+            index += 1;
+            low += 1;
+        }
+        high += 1;
+    }
+    combos
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ParseRangeError {
+    Card(ParseCardError),
+    InvalidHand(String),   // part of the range that isn't a hand, eg. "AKx"
+    InvalidSpan(String),   // span whose ends don't match, eg. "AKs-QJo"
+    InvalidWeight(String), // weight that isn't a number between 0 and 1
+}
+impl Display for ParseRangeError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ParseRangeError::Card(err) => write!(f, "{}", err),
+            ParseRangeError::InvalidHand(s) => write!(f, "'{}' is not a valid hand", s),
+            ParseRangeError::InvalidSpan(s) => write!(f, "'{}' is not a valid span of hands", s),
+            ParseRangeError::InvalidWeight(s) => {
+                write!(f, "'{}' is not a weight between 0 and 1", s)
+            }
+        }
+    }
+}
+impl Error for ParseRangeError {}
+impl From<ParseCardError> for ParseRangeError {
+    fn from(err: ParseCardError) -> Self {
+        ParseRangeError::Card(err)
+    }
+}
+
+/// which combos of two ranks a hand class has
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub(super) enum Kind {
+    Pair,
+    Suited,
+    Offsuit,
+    Any, // both suited and offsuit, eg. "AK"
+}
+
+/// A set of hole cards where each of the 1326 combos has a weight between 0 and 1,
+/// the fraction of the time the combo is played. Combos with weight 0 aren't in the range.
+#[derive(Clone, PartialEq, Debug)]
+pub struct Range {
+    weights: Vec<f32>, // weight of each combo by combo index
+}
+impl Range {
+    /// empty range
+    pub fn new() -> Range {
+        Range {
+            weights: vec![0.0; NUM_COMBOS],
+        }
+    }
+    /// range with every combo
+    pub fn full() -> Range {
+        Range {
+            weights: vec![1.0; NUM_COMBOS],
+        }
+    }
+    /// index of the combo, between 0 and `NUM_COMBOS`. the order of the cards doesn't matter
+    pub fn get_combo_index(hand: [Card; 2]) -> usize {
+        let [a, b] = hand.map(|card| card.get_index() as usize);
+        assert_ne!(a, b, "A combo needs two different cards");
+        let (low, high) = if a < b { (a, b) } else { (b, a) };
+        high * (high - 1) / 2 + low
+    }
+    /// inverse of `get_combo_index`
+    pub fn get_combo(index: usize) -> [Card; 2] {
+        COMBOS[index].map(Card::from_index)
+    }
+    pub fn get_weight(&self, hand: [Card; 2]) -> f32 {
+        self.weights[Range::get_combo_index(hand)]
+    }
+    /// weight is clamped between 0 and 1
+    pub fn set_weight(&mut self, hand: [Card; 2], weight: f32) {
+        self.weights[Range::get_combo_index(hand)] = weight.clamp(0.0, 1.0);
+    }
+    /// number of combos in the range, ignoring the weights
+    pub fn len(&self) -> usize {
+        self.weights.iter().filter(|&&weight| weight > 0.0).count()
+    }
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+    /// sum of the weights, the number of combos played on average
+    pub fn get_total_weight(&self) -> f64 {
+        self.weights.iter().map(|&weight| weight as f64).sum()
+    }
+    /// iterates the combos in the range with their weights
+    pub fn iter(&self) -> impl Iterator<Item = ([Card; 2], f32)> + '_ {
+        self.weights
+            .iter()
+            .enumerate()
+            .filter(|(_, &weight)| weight > 0.0)
+            .map(|(index, &weight)| (Range::get_combo(index), weight))
+    }
+    /// removes the combos that use any of the `dead` cards, eg. the board
+    pub fn remove_conflicts(&mut self, dead: CardSet) {
+        for (index, weight) in self.weights.iter_mut().enumerate() {
+            if COMBOS[index]
+                .iter()
+                .any(|&card| dead.contains(Card::from_index(card)))
+            {
+                *weight = 0.0;
+            }
+        }
+    }
+
+    /// combos of a hand class. ranks are rank indices with `high >= low`
+    pub(super) fn class_combos(high: u8, low: u8, kind: Kind) -> Vec<[Card; 2]> {
+        let mut combos = Vec::new();
+        for suit1 in 0..4 {
+            for suit2 in 0..4 {
+                let keep = match kind {
+                    Kind::Pair => suit1 < suit2,
+                    Kind::Suited => suit1 == suit2,
+                    Kind::Offsuit => suit1 != suit2,
+                    Kind::Any => true,
+                };
+                if keep {
+                    combos.push([
+                        Card::from_index(suit1 * 13 + high),
+                        Card::from_index(suit2 * 13 + low),
+                    ]);
+                }
+            }
+        }
+        combos
+    }
+    /// the weight of every combo of the class if they're all the same
+    fn get_class_weight(&self, high: u8, low: u8, kind: Kind) -> Option<f32> {
+        let combos = Range::class_combos(high, low, kind);
+        let weight = self.get_weight(combos[0]);
+        combos
+            .iter()
+            .all(|&combo| self.get_weight(combo) == weight)
+            .then_some(weight)
+    }
+
+    /// parse a hand class like "AKs", "AKo", "AK" or "TT".
+    /// returns the rank indices, highest first
+    pub(super) fn parse_class(s: &str) -> Result<(u8, u8, Kind), ParseRangeError> {
+        let invalid = || ParseRangeError::InvalidHand(s.into());
+        let chars: Vec<char> = s.chars().collect();
+        if chars.len() < 2 || chars.len() > 3 {
+            return Err(invalid());
+        }
+        let first = rank_index_from_char(chars[0])?;
+        let second = rank_index_from_char(chars[1])?;
+        let kind = match (
+            chars.get(2).map(|c| c.to_ascii_lowercase()),
+            first == second,
+        ) {
+            (None, true) => Kind::Pair,
+            (None, false) => Kind::Any,
+            (Some('s'), false) => Kind::Suited,
+            (Some('o'), false) => Kind::Offsuit,
+            _ => return Err(invalid()),
+        };
+        Ok((first.max(second), first.min(second), kind))
+    }
+    /// parse one part of a range without the weight, eg. "QQ+", "A5s-A2s", "AsKd"
+    fn parse_hands(s: &str) -> Result<Vec<[Card; 2]>, ParseRangeError> {
+        let chars: Vec<char> = s.chars().collect();
+        // a specific combo has a suit as its second character, which can't be a rank
+        if chars.len() == 4 && rank_index_from_char(chars[1]).is_err() {
+            let cards = Card::parse_list(s)?;
+            return Ok(vec![[cards[0], cards[1]]]);
+        }
+
+        let mut combos = Vec::new();
+        if let Some((start, end)) = s.split_once('-') {
+            let invalid = || ParseRangeError::InvalidSpan(s.into());
+            let (high1, low1, kind1) = Range::parse_class(start)?;
+            let (high2, low2, kind2) = Range::parse_class(end)?;
+            if kind1 != kind2 {
+                return Err(invalid());
+            }
+            if kind1 == Kind::Pair {
+                // "QQ-99"
+                for rank in high1.min(high2)..=high1.max(high2) {
+                    combos.extend(Range::class_combos(rank, rank, kind1));
+                }
+            } else {
+                // "A5s-A2s", the highest card stays the same
+                if high1 != high2 {
+                    return Err(invalid());
+                }
+                for low in low1.min(low2)..=low1.max(low2) {
+                    combos.extend(Range::class_combos(high1, low, kind1));
+                }
+            }
+        } else if let Some(class) = s.strip_suffix('+') {
+            let (high, low, kind) = Range::parse_class(class)?;
+            if kind == Kind::Pair {
+                // "QQ+" is every pair from queens up
+                for rank in high..13 {
+                    combos.extend(Range::class_combos(rank, rank, kind));
+                }
+            } else {
+                // "ATs+" raises the lower card up to below the higher card
+                for low in low..high {
+                    combos.extend(Range::class_combos(high, low, kind));
+                }
+            }
+        } else {
+            let (high, low, kind) = Range::parse_class(s)?;
+            combos.extend(Range::class_combos(high, low, kind));
+        }
+        Ok(combos)
+    }
+}
+impl Default for Range {
+    fn default() -> Self {
+        Range::new()
+    }
+}
+
+/// rank index (0 is deuce, 12 is ace) of a rank character
+pub(super) fn rank_index_from_char(c: char) -> Result<u8, ParseCardError> {
+    Ok(Card::new(Card::rank_from_char(c)?, Suit::Spade).get_rank_index())
+}
+/// rank character of a rank index
+pub(super) fn rank_index_to_char(index: u8) -> char {
+    Card::rank_to_char(Card::from_index(index).rank)
+}
+
+impl FromStr for Range {
+    type Err = ParseRangeError;
+
+    /// parts are separated by commas, each with an optional weight after a colon.
+    /// later parts overwrite the weights of earlier ones
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut range = Range::new();
+        for part in s.split(',').map(str::trim).filter(|part| !part.is_empty()) {
+            let (hands, weight) = match part.split_once(':') {
+                Some((hands, weight)) => {
+                    let invalid = || ParseRangeError::InvalidWeight(weight.into());
+                    let weight: f32 = weight.trim().parse().map_err(|_| invalid())?;
+                    if !(0.0..=1.0).contains(&weight) {
+                        return Err(invalid());
+                    }
+                    (hands.trim(), weight)
+                }
+                None => (part, 1.0),
+            };
+            for hand in Range::parse_hands(hands)? {
+                range.set_weight(hand, weight);
+            }
+        }
+        Ok(range)
+    }
+}
+
+impl Display for Range {
+    /// shortest notation for the range, pairs first, then by the highest card.
+    /// consecutive classes with the same weight are joined, eg. "QQ+", "A5s-A2s"
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut parts: Vec<String> = Vec::new();
+        let with_weight = |hands: String, weight: f32| {
+            if weight == 1.0 {
+                hands
+            } else {
+                format!("{}:{}", hands, weight)
+            }
+        };
+        // classes with different weights between combos are written combo by combo
+        let push_combos = |parts: &mut Vec<String>, high: u8, low: u8, kind: Kind| {
+            for combo in Range::class_combos(high, low, kind) {
+                let weight = self.get_weight(combo);
+                if weight > 0.0 {
+                    parts.push(with_weight(format!("{}{}", combo[0], combo[1]), weight));
+                }
+            }
+        };
+        // `ranks` goes from highest to lowest, the first element is the top of the run.
+        // `name` writes a class given its varying rank
+        let push_runs = |parts: &mut Vec<String>,
+                         ranks: &[u8],
+                         class_weight: &dyn Fn(u8) -> Option<f32>,
+                         combos: &dyn Fn(&mut Vec<String>, u8),
+                         name: &dyn Fn(u8) -> String| {
+            let mut i = 0;
+            while i < ranks.len() {
+                let weight = match class_weight(ranks[i]) {
+                    Some(weight) => weight,
+                    None => {
+                        combos(parts, ranks[i]);
+                        i += 1;
+                        continue;
+                    }
+                };
+                let mut end = i;
+                while end + 1 < ranks.len() && class_weight(ranks[end + 1]) == Some(weight) {
+                    end += 1;
+                }
+                if weight > 0.0 {
+                    let hands = if end == i {
+                        name(ranks[i])
+                    } else if i == 0 {
+                        format!("{}+", name(ranks[end]))
+                    } else {
+                        format!("{}-{}", name(ranks[i]), name(ranks[end]))
+                    };
+                    parts.push(with_weight(hands, weight));
+                }
+                i = end + 1;
+            }
+        };
+
+        let pair_ranks: Vec<u8> = (0..13).rev().collect();
+        push_runs(
+            &mut parts,
+            &pair_ranks,
+            &|rank| self.get_class_weight(rank, rank, Kind::Pair),
+            &|parts, rank| push_combos(parts, rank, rank, Kind::Pair),
+            &|rank| format!("{0}{0}", rank_index_to_char(rank)),
+        );
+        for high in (1..13).rev() {
+            let lows: Vec<u8> = (0..high).rev().collect();
+            for (kind, suffix) in [(Kind::Suited, 's'), (Kind::Offsuit, 'o')] {
+                push_runs(
+                    &mut parts,
+                    &lows,
+                    &|low| self.get_class_weight(high, low, kind),
+                    &|parts, low| push_combos(parts, high, low, kind),
+                    &|low| {
+                        format!(
+                            "{}{}{}",
+                            rank_index_to_char(high),
+                            rank_index_to_char(low),
+                            suffix
+                        )
+                    },
+                );
+            }
+        }
+        write!(f, "{}", parts.join(", "))
+    }
+}
+
+/// ranges are written in range notation
+impl Serialize for Range {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+impl<'de> Deserialize<'de> for Range {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let s = String::deserialize(deserializer)?;
+        s.parse().map_err(de::Error::custom)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_and_display() {
+        let notation = "QQ+, AKs, A5s-A2s, KQo, 76s:0.5";
+        let range: Range = notation.parse().unwrap();
+        assert_eq!(range.len(), 18 + 4 + 16 + 12 + 4);
+        assert_eq!(range.get_total_weight(), 52.0);
+        assert_eq!(
+            range.get_weight(Card::parse_list("7h6h").unwrap().try_into().unwrap()),
+            0.5
+        );
+        assert_eq!(range.to_string(), notation);
+
+        // "AK" has both suited and offsuit combos, later parts overwrite earlier ones
+        let range: Range = "AK, ATs+:0.25, 22-44, AsKd:0".parse().unwrap();
+        assert_eq!(
+            range.to_string(),
+            "44-22, ATs+:0.25, AsKc, AsKh, AcKs, AcKd, AcKh, AdKs, AdKc, AdKh, AhKs, AhKc, AhKd"
+        );
+        assert_eq!(range.to_string().parse::<Range>().unwrap(), range);
+
+        assert_eq!(Range::full().len(), NUM_COMBOS);
+        assert!(Range::full()
+            .to_string()
+            .starts_with("22+, A2s+, A2o+, K2s+"));
+        assert!("AKx".parse::<Range>().is_err());
+        assert!("AKs-QJs".parse::<Range>().is_err());
+        assert!("AA:2".parse::<Range>().is_err());
+    }
+
+    #[test]
+    fn combos_and_conflicts() {
+        for index in 0..NUM_COMBOS {
+            assert_eq!(Range::get_combo_index(Range::get_combo(index)), index);
+        }
+        let mut range: Range = "AA, KK".parse().unwrap();
+        range.remove_conflicts("As Kd Kc".parse().unwrap());
+        assert_eq!(range.len(), 3 + 1);
+        assert_eq!(range.to_string(), "AcAd, AcAh, AdAh, KsKh");
+    }
+}
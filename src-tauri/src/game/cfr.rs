@@ -0,0 +1,541 @@
+//! Counterfactual regret minimization for two player zero-sum games with imperfect information.
+//! The average strategy of the players converges to a Nash equilibrium
+
+use std::{collections::HashMap, hash::Hash};
+
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+
+/// what happens at a state of the game
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Node {
+    Terminal,
+    Chance,        // eg. dealing cards
+    Player(usize), // index of the player to act, 0 or 1
+}
+
+/// A game tree, states are the histories of actions from the root
+pub trait ExtensiveGame {
+    type State: Clone;
+    type Action: Clone;
+    /// what the player to act knows, states the player can't tell apart have the same info set
+    type InfoSet: Clone + Eq + Hash;
+
+    fn get_root(&self) -> Self::State;
+    fn get_node(&self, state: &Self::State) -> Node;
+    /// actions of the player to act, in the same order for every state of an info set
+    fn get_actions(&self, state: &Self::State) -> Vec<Self::Action>;
+    /// outcomes of a chance node with their probabilities
+    fn get_chance_outcomes(&self, state: &Self::State) -> Vec<(Self::Action, f64)>;
+    /// Samples an outcome of a chance node by its probability. games with too many outcomes
+    /// to list, like dealing hold'em, override it and can only be solved by sampling
+    fn sample_chance<R: Rng>(&self, state: &Self::State, rng: &mut R) -> Self::Action {
+        let outcomes = self.get_chance_outcomes(state);
+        let mut target: f64 = rng.gen();
+        for (outcome, probability) in outcomes.iter() {
+            if target < *probability {
+                return outcome.clone();
+            }
+            target -= probability;
+        }
+        outcomes.last().unwrap().0.clone()
+    }
+    fn get_info_set(&self, state: &Self::State) -> Self::InfoSet;
+    fn apply(&self, state: &Self::State, action: &Self::Action) -> Self::State;
+    /// payoff of the first player at a terminal state, the second player gets the negative
+    fn get_payoff(&self, state: &Self::State) -> f64;
+    /// hash of what the tree depends on, so a strategy isn't used for another tree.
+    /// games without a config keep the default
+    fn get_hash(&self) -> u64 {
+        0
+    }
+}
+
+/// how regrets and the average strategy are accumulated, `t` is the iteration from 1
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Debug)]
+pub enum CfrVariant {
+    Vanilla,
+    CfrPlus, // negative regrets are reset to 0, the average strategy is weighted by t
+    Linear,  // regrets and the average strategy are weighted by t
+    // after iteration t, positive regrets are scaled by t^α / (t^α + 1), negative ones
+    // by t^β / (t^β + 1) and the average strategy by (t / (t + 1))^γ
+    Discounted { alpha: f64, beta: f64, gamma: f64 },
+}
+impl Default for CfrVariant {
+    /// discounted with the parameters that work best in the DCFR paper
+    fn default() -> Self {
+        CfrVariant::Discounted {
+            alpha: 1.5,
+            beta: 0.0,
+            gamma: 2.0,
+        }
+    }
+}
+
+/// accumulated regrets and strategies of an info set
+#[derive(Clone, Debug)]
+pub struct InfoSetNode {
+    regrets: Vec<f64>,
+    strategy_sum: Vec<f64>, // sum of the strategies weighted by the player's reach
+    // regrets of the traversal, added to `regrets` after it so the strategy
+    // is the same at every state of the info set
+    new_regrets: Vec<f64>,
+}
+impl InfoSetNode {
+    fn new(num_action: usize) -> InfoSetNode {
+        InfoSetNode {
+            regrets: vec![0.0; num_action],
+            strategy_sum: vec![0.0; num_action],
+            new_regrets: vec![0.0; num_action],
+        }
+    }
+    /// regret matching, in proportion to the positive regrets
+    pub fn get_strategy(&self) -> Vec<f64> {
+        normalize(self.regrets.iter().map(|&regret| regret.max(0.0)).collect())
+    }
+    /// the average strategy, which converges to an equilibrium
+    pub fn get_average_strategy(&self) -> Vec<f64> {
+        normalize(self.strategy_sum.clone())
+    }
+    pub fn get_regrets(&self) -> &[f64] {
+        &self.regrets
+    }
+}
+
+/// divides by the sum, uniform if the sum is 0
+pub(super) fn normalize(mut weights: Vec<f64>) -> Vec<f64> {
+    let total: f64 = weights.iter().sum();
+    let num_weight = weights.len() as f64;
+    for weight in weights.iter_mut() {
+        *weight = if total > 0.0 {
+            *weight / total
+        } else {
+            1.0 / num_weight
+        };
+    }
+    weights
+}
+
+/// Solves a game by iterating CFR with alternating updates,
+/// each iteration updates the first player then the second
+pub struct Cfr<G: ExtensiveGame> {
+    game: G,
+    variant: CfrVariant,
+    info_sets: HashMap<G::InfoSet, InfoSetNode>,
+    iterations: usize,
+    regret_weight: f64, // total weight of the iterations in the regrets
+}
+impl<G: ExtensiveGame> Cfr<G> {
+    pub fn new(game: G, variant: CfrVariant) -> Cfr<G> {
+        Cfr {
+            game,
+            variant,
+            info_sets: HashMap::new(),
+            iterations: 0,
+            regret_weight: 0.0,
+        }
+    }
+    pub fn get_game(&self) -> &G {
+        &self.game
+    }
+    pub fn get_iterations(&self) -> usize {
+        self.iterations
+    }
+    pub fn get_info_sets(&self) -> &HashMap<G::InfoSet, InfoSetNode> {
+        &self.info_sets
+    }
+    /// average strategy at an info set, None if it hasn't been reached
+    pub fn get_average_strategy(&self, info_set: &G::InfoSet) -> Option<Vec<f64>> {
+        self.info_sets
+            .get(info_set)
+            .map(InfoSetNode::get_average_strategy)
+    }
+
+    pub fn iterate(&mut self, iterations: usize) {
+        for _ in 0..iterations {
+            self.iterations += 1;
+            for player in 0..2 {
+                let root = self.game.get_root();
+                self.traverse(&root, player, 1.0, 1.0);
+                for node in self.info_sets.values_mut() {
+                    for (regret, new_regret) in node.regrets.iter_mut().zip(&mut node.new_regrets) {
+                        *regret += *new_regret;
+                        *new_regret = 0.0;
+                        if self.variant == CfrVariant::CfrPlus {
+                            *regret = regret.max(0.0);
+                        }
+                    }
+                }
+            }
+            self.regret_weight += match self.variant {
+                CfrVariant::Linear => self.iterations as f64,
+                _ => 1.0,
+            };
+            if let CfrVariant::Discounted { alpha, beta, gamma } = self.variant {
+                let t = self.iterations as f64;
+                let positive = t.powf(alpha) / (t.powf(alpha) + 1.0);
+                let negative = t.powf(beta) / (t.powf(beta) + 1.0);
+                let average = (t / (t + 1.0)).powf(gamma);
+                self.regret_weight *= positive;
+                for node in self.info_sets.values_mut() {
+                    for regret in node.regrets.iter_mut() {
+                        *regret *= if *regret > 0.0 { positive } else { negative };
+                    }
+                    for sum in node.strategy_sum.iter_mut() {
+                        *sum *= average;
+                    }
+                }
+            }
+        }
+    }
+
+    /// updates the regrets of `player`, returns the expected payoff of `player`.
+    /// `reach` is the player's probability of getting to the state,
+    /// `others_reach` is the opponent's and chance's
+    fn traverse(&mut self, state: &G::State, player: usize, reach: f64, others_reach: f64) -> f64 {
+        let sign = if player == 0 { 1.0 } else { -1.0 };
+        let acting = match self.game.get_node(state) {
+            Node::Terminal => return sign * self.game.get_payoff(state),
+            Node::Chance => {
+                return self
+                    .game
+                    .get_chance_outcomes(state)
+                    .iter()
+                    .map(|(outcome, probability)| {
+                        let next = self.game.apply(state, outcome);
+                        probability
+                            * self.traverse(&next, player, reach, others_reach * probability)
+                    })
+                    .sum();
+            }
+            Node::Player(acting) => acting,
+        };
+        let actions = self.game.get_actions(state);
+        let info_set = self.game.get_info_set(state);
+        let strategy = self
+            .info_sets
+            .entry(info_set.clone())
+            .or_insert_with(|| InfoSetNode::new(actions.len()))
+            .get_strategy();
+
+        if acting != player {
+            return actions
+                .iter()
+                .zip(&strategy)
+                .map(|(action, &probability)| {
+                    let next = self.game.apply(state, action);
+                    probability * self.traverse(&next, player, reach, others_reach * probability)
+                })
+                .sum();
+        }
+        let values: Vec<f64> = actions
+            .iter()
+            .zip(&strategy)
+            .map(|(action, &probability)| {
+                let next = self.game.apply(state, action);
+                self.traverse(&next, player, reach * probability, others_reach)
+            })
+            .collect();
+        let value: f64 = values.iter().zip(&strategy).map(|(v, p)| v * p).sum();
+
+        let t = self.iterations as f64;
+        let (regret_weight, strategy_weight) = match self.variant {
+            CfrVariant::Vanilla | CfrVariant::Discounted { .. } => (1.0, 1.0),
+            CfrVariant::CfrPlus => (1.0, t),
+            CfrVariant::Linear => (t, t),
+        };
+        let node = self.info_sets.get_mut(&info_set).unwrap();
+        for (index, action_value) in values.iter().enumerate() {
+            node.new_regrets[index] += regret_weight * others_reach * (action_value - value);
+            node.strategy_sum[index] += strategy_weight * reach * strategy[index];
+        }
+        value
+    }
+
+    /// expected payoff of the first player when both play the average strategy
+    pub fn get_value(&self) -> f64 {
+        get_value(&self.game, &|info_set| self.get_average_strategy(info_set))
+    }
+    /// expected payoff of `player` playing a best response against the average strategy
+    pub fn get_best_response_value(&self, player: usize) -> f64 {
+        get_best_response_value(
+            &self.game,
+            &|info_set| self.get_average_strategy(info_set),
+            player,
+        )
+    }
+    /// how much a best response wins against the average strategy, see `get_exploitability`
+    pub fn get_exploitability(&self) -> f64 {
+        get_exploitability(&self.game, &|info_set| self.get_average_strategy(info_set))
+    }
+
+    /// sum of the players' largest positive regret at each info set, divided by the total
+    /// weight of the iterations. bounds how far the average strategy is from an equilibrium
+    pub fn get_average_regret(&self) -> f64 {
+        let total: f64 = self
+            .info_sets
+            .values()
+            .map(|node| node.regrets.iter().fold(0.0, |max: f64, &r| max.max(r)))
+            .sum();
+        total / self.regret_weight.max(1.0)
+    }
+}
+
+/// probabilities of the actions at an info set, None if the strategy doesn't have it
+pub type Strategy<'a, G> = dyn Fn(&<G as ExtensiveGame>::InfoSet) -> Option<Vec<f64>> + 'a;
+
+/// expected payoffs of a strategy and best responses to it
+struct Evaluator<'a, G: ExtensiveGame> {
+    game: &'a G,
+    strategy: &'a Strategy<'a, G>,
+}
+impl<'a, G: ExtensiveGame> Evaluator<'a, G> {
+    /// strategy at the state, uniform if the strategy doesn't have the info set
+    fn get_state_strategy(&self, state: &G::State, num_action: usize) -> Vec<f64> {
+        (self.strategy)(&self.game.get_info_set(state))
+            .unwrap_or_else(|| vec![1.0 / num_action as f64; num_action])
+    }
+    fn get_state_value(&self, state: &G::State) -> f64 {
+        match self.game.get_node(state) {
+            Node::Terminal => self.game.get_payoff(state),
+            Node::Chance => self
+                .game
+                .get_chance_outcomes(state)
+                .iter()
+                .map(|(outcome, probability)| {
+                    probability * self.get_state_value(&self.game.apply(state, outcome))
+                })
+                .sum(),
+            Node::Player(_) => {
+                let actions = self.game.get_actions(state);
+                let strategy = self.get_state_strategy(state, actions.len());
+                actions
+                    .iter()
+                    .zip(strategy)
+                    .map(|(action, probability)| {
+                        probability * self.get_state_value(&self.game.apply(state, action))
+                    })
+                    .sum()
+            }
+        }
+    }
+
+    fn get_best_response_value(&self, player: usize) -> f64 {
+        // states of each of the player's info sets with the opponent's and chance's reach
+        let mut info_set_states: HashMap<G::InfoSet, Vec<(G::State, f64)>> = HashMap::new();
+        self.collect_states(&self.game.get_root(), player, 1.0, &mut info_set_states);
+        let mut choices = HashMap::new();
+        self.get_best_response_state_value(
+            &self.game.get_root(),
+            player,
+            &info_set_states,
+            &mut choices,
+        )
+    }
+    fn collect_states(
+        &self,
+        state: &G::State,
+        player: usize,
+        others_reach: f64,
+        info_set_states: &mut HashMap<G::InfoSet, Vec<(G::State, f64)>>,
+    ) {
+        match self.game.get_node(state) {
+            Node::Terminal => {}
+            Node::Chance => {
+                for (outcome, probability) in self.game.get_chance_outcomes(state) {
+                    let next = self.game.apply(state, &outcome);
+                    self.collect_states(&next, player, others_reach * probability, info_set_states);
+                }
+            }
+            Node::Player(acting) => {
+                let actions = self.game.get_actions(state);
+                if acting == player {
+                    info_set_states
+                        .entry(self.game.get_info_set(state))
+                        .or_default()
+                        .push((state.clone(), others_reach));
+                    for action in actions.iter() {
+                        let next = self.game.apply(state, action);
+                        self.collect_states(&next, player, others_reach, info_set_states);
+                    }
+                } else {
+                    let strategy = self.get_state_strategy(state, actions.len());
+                    for (action, probability) in actions.iter().zip(strategy) {
+                        let next = self.game.apply(state, action);
+                        self.collect_states(
+                            &next,
+                            player,
+                            others_reach * probability,
+                            info_set_states,
+                        );
+                    }
+                }
+            }
+        }
+    }
+    /// value of a state for `player` best responding, `choices` are the actions picked
+    /// at the player's info sets, by the value summed over the states of the info set
+    fn get_best_response_state_value(
+        &self,
+        state: &G::State,
+        player: usize,
+        info_set_states: &HashMap<G::InfoSet, Vec<(G::State, f64)>>,
+        choices: &mut HashMap<G::InfoSet, usize>,
+    ) -> f64 {
+        let sign = if player == 0 { 1.0 } else { -1.0 };
+        match self.game.get_node(state) {
+            Node::Terminal => sign * self.game.get_payoff(state),
+            Node::Chance => self
+                .game
+                .get_chance_outcomes(state)
+                .iter()
+                .map(|(outcome, probability)| {
+                    let next = self.game.apply(state, outcome);
+                    probability
+                        * self.get_best_response_state_value(
+                            &next,
+                            player,
+                            info_set_states,
+                            choices,
+                        )
+                })
+                .sum(),
+            Node::Player(acting) => {
+                let actions = self.game.get_actions(state);
+                if acting != player {
+                    let strategy = self.get_state_strategy(state, actions.len());
+                    return actions
+                        .iter()
+                        .zip(strategy)
+                        .map(|(action, probability)| {
+                            let next = self.game.apply(state, action);
+                            probability
+                                * self.get_best_response_state_value(
+                                    &next,
+                                    player,
+                                    info_set_states,
+                                    choices,
+                                )
+                        })
+                        .sum();
+                }
+                let info_set = self.game.get_info_set(state);
+                let choice = match choices.get(&info_set) {
+                    Some(&choice) => choice,
+                    None => {
+                        let mut action_values = vec![0.0; actions.len()];
+                        for (other_state, reach) in &info_set_states[&info_set] {
+                            for (index, action) in actions.iter().enumerate() {
+                                let next = self.game.apply(other_state, action);
+                                action_values[index] += reach
+                                    * self.get_best_response_state_value(
+                                        &next,
+                                        player,
+                                        info_set_states,
+                                        choices,
+                                    );
+                            }
+                        }
+                        let choice = (0..actions.len())
+                            .max_by(|&a, &b| {
+                                action_values[a].partial_cmp(&action_values[b]).unwrap()
+                            })
+                            .unwrap();
+                        choices.insert(info_set, choice);
+                        choice
+                    }
+                };
+                let next = self.game.apply(state, &actions[choice]);
+                self.get_best_response_state_value(&next, player, info_set_states, choices)
+            }
+        }
+    }
+}
+
+/// expected payoff of the first player when both play `strategy`
+pub fn get_value<G: ExtensiveGame>(game: &G, strategy: &Strategy<G>) -> f64 {
+    Evaluator { game, strategy }.get_state_value(&game.get_root())
+}
+
+/// expected payoff of `player` playing a best response against `strategy`
+pub fn get_best_response_value<G: ExtensiveGame>(
+    game: &G,
+    strategy: &Strategy<G>,
+    player: usize,
+) -> f64 {
+    Evaluator { game, strategy }.get_best_response_value(player)
+}
+
+/// How much a best response wins against `strategy`, averaged over both players.
+/// 0 at a Nash equilibrium
+pub fn get_exploitability<G: ExtensiveGame>(game: &G, strategy: &Strategy<G>) -> f64 {
+    (get_best_response_value(game, strategy, 0) + get_best_response_value(game, strategy, 1)) / 2.0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// rock paper scissors where winning with rock pays 2,
+    /// the second player picks without seeing the first player's pick
+    struct RockPaperScissors;
+    impl ExtensiveGame for RockPaperScissors {
+        type State = Vec<usize>;
+        type Action = usize;
+        type InfoSet = usize;
+
+        fn get_root(&self) -> Vec<usize> {
+            Vec::new()
+        }
+        fn get_node(&self, state: &Vec<usize>) -> Node {
+            match state.len() {
+                2 => Node::Terminal,
+                player => Node::Player(player),
+            }
+        }
+        fn get_actions(&self, _state: &Vec<usize>) -> Vec<usize> {
+            vec![0, 1, 2]
+        }
+        fn get_chance_outcomes(&self, _state: &Vec<usize>) -> Vec<(usize, f64)> {
+            Vec::new()
+        }
+        fn get_info_set(&self, state: &Vec<usize>) -> usize {
+            state.len()
+        }
+        fn apply(&self, state: &Vec<usize>, action: &usize) -> Vec<usize> {
+            let mut next = state.clone();
+            next.push(*action);
+            next
+        }
+        fn get_payoff(&self, state: &Vec<usize>) -> f64 {
+            const PAYOFFS: [[f64; 3]; 3] = [[0.0, -1.0, 2.0], [1.0, 0.0, -1.0], [-2.0, 1.0, 0.0]];
+            PAYOFFS[state[0]][state[1]]
+        }
+    }
+
+    #[test]
+    fn converges_to_equilibrium() {
+        for variant in [
+            CfrVariant::Vanilla,
+            CfrVariant::CfrPlus,
+            CfrVariant::Linear,
+            CfrVariant::default(),
+        ] {
+            let mut cfr = Cfr::new(RockPaperScissors, variant);
+            cfr.iterate(1);
+            let first_exploitability = cfr.get_exploitability();
+            cfr.iterate(9_999);
+            // paper twice as often as rock and scissors
+            for player in 0..2 {
+                let strategy = cfr.get_average_strategy(&player).unwrap();
+                for (probability, expected) in strategy.iter().zip([0.25, 0.5, 0.25]) {
+                    assert!((probability - expected).abs() < 0.02, "{:?}", variant);
+                }
+            }
+            assert!(cfr.get_exploitability() < 0.01, "{:?}", variant);
+            assert!(cfr.get_exploitability() < first_exploitability);
+            assert!(cfr.get_value().abs() < 0.01);
+            assert!(cfr.get_average_regret() < 0.05, "{:?}", variant);
+        }
+    }
+}
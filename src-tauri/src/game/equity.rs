@@ -0,0 +1,586 @@
+use rand::{
+    distributions::{Distribution, WeightedIndex},
+    rngs::StdRng,
+    SeedableRng,
+};
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use super::card::{evaluate, Card, CardSet, HandStrength, Range};
+
+/// cards a player is holding in an equity calculation
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub enum Holding {
+    Hand([Card; 2]),
+    Range(Range), // hands are dealt in proportion to their weights
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug)]
+pub struct EquityCfg {
+    pub samples: usize,    // number of boards to sample
+    pub seed: Option<u64>, // random if None
+    // enumerate every run-out instead of sampling if there're at most this many
+    pub exact_threshold: usize,
+}
+impl Default for EquityCfg {
+    fn default() -> Self {
+        EquityCfg {
+            samples: 100_000,
+            seed: None,
+            exact_threshold: 200_000,
+        }
+    }
+}
+
+/// results of an equity calculation, in percentages for each holding
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct Equity {
+    win: Vec<f64>,    // winning alone
+    tie: Vec<f64>,    // splitting the pot
+    equity: Vec<f64>, // share of the pot won on average
+    // boards won alone and tied, counted with the weights of the hands from ranges
+    win_count: Vec<f64>,
+    tie_count: Vec<f64>,
+    samples: u64, // number of boards, all possible boards if exact
+    exact: bool,  // enumerated every board instead of sampling
+}
+impl Equity {
+    pub fn get_win_count(&self) -> &[f64] {
+        &self.win_count
+    }
+    pub fn get_tie_count(&self) -> &[f64] {
+        &self.tie_count
+    }
+    pub fn is_exact(&self) -> bool {
+        self.exact
+    }
+    pub fn get_win(&self) -> &[f64] {
+        &self.win
+    }
+    pub fn get_tie(&self) -> &[f64] {
+        &self.tie
+    }
+    pub fn get_equity(&self) -> &[f64] {
+        &self.equity
+    }
+    pub fn get_samples(&self) -> u64 {
+        self.samples
+    }
+}
+
+/// equity of a hand from the hero's range
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct HandEquity {
+    hand: [Card; 2],
+    weight: f32,         // weight of the hand in the range
+    equity: f64,         // percentage
+    villain_weight: f64, // how likely the villains' hands are given the hand, see `range_vs_range`
+}
+impl HandEquity {
+    pub fn get_hand(&self) -> [Card; 2] {
+        self.hand
+    }
+    pub fn get_weight(&self) -> f32 {
+        self.weight
+    }
+    pub fn get_equity(&self) -> f64 {
+        self.equity
+    }
+}
+
+/// results of ranges against each other, the first range is the hero's
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct RangeEquity {
+    equity: Vec<f64>, // percentage for each range
+    hands: Vec<HandEquity>,
+}
+impl RangeEquity {
+    pub fn get_equity(&self) -> &[f64] {
+        &self.equity
+    }
+    /// each hand of the hero's range that can be dealt
+    pub fn get_hands(&self) -> &[HandEquity] {
+        &self.hands
+    }
+    /// Fraction of the hero's range with an equity in each of `num_buckets` equal buckets,
+    /// eg. with 10 buckets the first one has the hands with 0% to 10% equity.
+    /// hands count with their weight in the range and the weight of the villains' hands.
+    /// empty without buckets, all 0 if the hands don't weigh anything
+    pub fn get_histogram(&self, num_buckets: usize) -> Vec<f64> {
+        let mut histogram = vec![0.0; num_buckets];
+        if num_buckets == 0 {
+            return histogram;
+        }
+        let mut total = 0.0;
+        for hand in &self.hands {
+            let weight = hand.weight as f64 * hand.villain_weight;
+            let bucket = (hand.equity / 100.0 * num_buckets as f64) as usize;
+            histogram[bucket.min(num_buckets - 1)] += weight;
+            total += weight;
+        }
+        if total == 0.0 {
+            return histogram;
+        }
+        for fraction in histogram.iter_mut() {
+            *fraction /= total;
+        }
+        histogram
+    }
+}
+
+/// counts the wins, ties and pot shares while going through boards
+struct Tally {
+    wins: Vec<f64>,
+    ties: Vec<f64>,
+    shares: Vec<f64>,
+    total_weight: f64,
+    total: u64,
+}
+impl Tally {
+    fn new(num_holding: usize) -> Tally {
+        Tally {
+            wins: vec![0.0; num_holding],
+            ties: vec![0.0; num_holding],
+            shares: vec![0.0; num_holding],
+            total_weight: 0.0,
+            total: 0,
+        }
+    }
+    /// add a showdown between the holes on a full board,
+    /// `weight` is how likely the holes are to be dealt
+    fn add(&mut self, holes: &[CardSet], board: CardSet, weight: f64) {
+        let strengths: Vec<HandStrength> =
+            holes.iter().map(|&hole| evaluate(hole | board)).collect();
+        let best = *strengths.iter().max().unwrap();
+        let num_winner = strengths.iter().filter(|&&s| s == best).count();
+        for (index, &strength) in strengths.iter().enumerate() {
+            if strength == best {
+                if num_winner == 1 {
+                    self.wins[index] += weight;
+                } else {
+                    self.ties[index] += weight;
+                }
+                self.shares[index] += weight / num_winner as f64;
+            }
+        }
+        self.total_weight += weight;
+        self.total += 1;
+    }
+    fn into_equity(self, exact: bool) -> Equity {
+        let percentage = |count: f64| count * 100.0 / self.total_weight;
+        Equity {
+            win: self.wins.iter().map(|&c| percentage(c)).collect(),
+            tie: self.ties.iter().map(|&c| percentage(c)).collect(),
+            equity: self.shares.iter().map(|&c| percentage(c)).collect(),
+            win_count: self.wins,
+            tie_count: self.ties,
+            samples: self.total,
+            exact,
+        }
+    }
+}
+
+/// checks the number of holdings, the size of the board, that no card is used twice and
+/// that there're enough cards left to deal. returns all the cards that are known
+fn check_cards(holdings: &[Holding], board: &[Card], dead: &[Card]) -> Result<CardSet, String> {
+    if !(2..=10).contains(&holdings.len()) {
+        return Err(format!(
+            "Need 2 to 10 players to calculate equity, got {}",
+            holdings.len()
+        ));
+    }
+    if board.len() > 5 {
+        return Err(format!(
+            "Board has {} cards, can't have more than 5",
+            board.len()
+        ));
+    }
+    let mut known = CardSet::new();
+    let hand_cards = holdings.iter().flat_map(|holding| match holding {
+        Holding::Hand(hole) => hole.to_vec(),
+        Holding::Range(_) => Vec::new(),
+    });
+    for card in hand_cards
+        .chain(board.iter().copied())
+        .chain(dead.iter().copied())
+    {
+        if !known.insert(card) {
+            return Err(format!("{} is used more than once", card));
+        }
+    }
+    for holding in holdings {
+        if let Holding::Range(range) = holding {
+            if range.is_empty() {
+                return Err("Range doesn't have any hands".into());
+            }
+        }
+    }
+    // the rest of the board and a hand for each range are dealt from the unknown cards
+    let num_range = holdings
+        .iter()
+        .filter(|holding| matches!(holding, Holding::Range(_)))
+        .count();
+    let num_needed = 5 - board.len() + 2 * num_range;
+    if 52 - known.len() < num_needed {
+        return Err(format!(
+            "{} cards are left, need {} to deal the board and the ranges",
+            52 - known.len(),
+            num_needed
+        ));
+    }
+    Ok(known)
+}
+
+/// Estimates the equity of each holding by dealing random boards.
+/// `board` is the community cards dealt so far and `dead` are cards that can't be dealt.
+pub fn monte_carlo(
+    holdings: &[Holding],
+    board: &[Card],
+    dead: &[Card],
+    cfg: EquityCfg,
+) -> Result<Equity, String> {
+    let known = check_cards(holdings, board, dead)?;
+    if cfg.samples == 0 {
+        return Err("Need at least 1 sample to estimate equity".into());
+    }
+    let board: CardSet = board.iter().collect();
+    let mut rng = match cfg.seed {
+        Some(seed) => StdRng::seed_from_u64(seed),
+        None => StdRng::from_entropy(),
+    };
+
+    // hands of each range that don't conflict with the known cards, to pick by weight
+    // fixed hands have a single hand
+    let mut range_hands = Vec::new();
+    for holding in holdings {
+        range_hands.push(match holding {
+            Holding::Hand(cards) => (vec![cards.iter().collect()], None),
+            Holding::Range(range) => {
+                let mut range = range.clone();
+                range.remove_conflicts(known);
+                if range.is_empty() {
+                    return Err(
+                        "Range doesn't have any hands left after removing the known cards".into(),
+                    );
+                }
+                let (hands, weights): (Vec<CardSet>, Vec<f32>) = range
+                    .iter()
+                    .map(|(hand, weight)| (hand.iter().collect::<CardSet>(), weight))
+                    .unzip();
+                (hands, Some(WeightedIndex::new(weights).unwrap()))
+            }
+        });
+    }
+
+    let mut tally = Tally::new(holdings.len());
+    let mut holes = vec![CardSet::EMPTY; holdings.len()];
+    // give up on a sample after failing to deal non conflicting ranges this many times
+    const MAX_ATTEMPTS: usize = 1000;
+    let mut failed_attempts = 0;
+    while tally.total < cfg.samples as u64 {
+        // deal a hand from each range that doesn't conflict with the known cards
+        let mut used = known;
+        let mut conflicted = false;
+        for (hole, (hands, weights)) in holes.iter_mut().zip(&range_hands) {
+            *hole = match weights {
+                None => hands[0],
+                Some(weights) => {
+                    let cards = hands[weights.sample(&mut rng)];
+                    if !cards.is_disjoint(used) {
+                        conflicted = true;
+                        break;
+                    }
+                    used = used | cards;
+                    cards
+                }
+            };
+        }
+        if conflicted {
+            failed_attempts += 1;
+            if failed_attempts >= MAX_ATTEMPTS {
+                return Err("Can't deal hands from the ranges without conflicts".into());
+            }
+            continue;
+        }
+        failed_attempts = 0;
+        // deal the rest of the board
+        let run_out = CardSet::draw_excluding(used, 5 - board.len(), &mut rng);
+        tally.add(&holes, board | run_out, 1.0);
+    }
+    Ok(tally.into_equity(false))
+}
+
+/// number of ways to choose `k` out of `n`
+fn choose(n: usize, k: usize) -> usize {
+    if k > n {
+        return 0;
+    }
+    (0..k).fold(1, |result, i| result * (n - i) / (i + 1))
+}
+
+/// upper bound of the number of boards `exact` goes through,
+/// every hand of every range times every run-out
+fn count_run_outs(holdings: &[Holding], known: CardSet, board_len: usize) -> usize {
+    let mut num_hole_combination: usize = 1;
+    let mut num_unknown = 52 - known.len();
+    for holding in holdings {
+        if let Holding::Range(range) = holding {
+            num_hole_combination = num_hole_combination.saturating_mul(range.len());
+            num_unknown -= 2;
+        }
+    }
+    num_hole_combination.saturating_mul(choose(num_unknown, 5 - board_len))
+}
+
+/// Calculates the exact equity of each holding by going through every possible run-out
+/// of the board, and every combination of hands from the ranges.
+/// `board` is the community cards dealt so far and `dead` are cards that can't be dealt.
+pub fn exact(holdings: &[Holding], board: &[Card], dead: &[Card]) -> Result<Equity, String> {
+    /// deal a hand to each holding starting from `index`, then go through the run-outs
+    fn recurse(
+        holdings: &[Holding],
+        index: usize,
+        holes: &mut Vec<CardSet>,
+        used: CardSet,
+        board: CardSet,
+        weight: f64,
+        tally: &mut Tally,
+    ) {
+        if index == holdings.len() {
+            (!used).for_each_subset(5 - board.len(), &mut |run_out| {
+                tally.add(holes, board | run_out, weight);
+            });
+            return;
+        }
+        let hands: Vec<(CardSet, f32)> = match &holdings[index] {
+            Holding::Hand(cards) => vec![(cards.iter().collect(), 1.0)],
+            Holding::Range(range) => range
+                .iter()
+                .map(|(cards, weight)| (cards.iter().collect(), weight))
+                .collect(),
+        };
+        for (hand, hand_weight) in hands {
+            // hands fixed by `Holding::Hand` are already in `used`
+            if matches!(holdings[index], Holding::Range(_)) && !hand.is_disjoint(used) {
+                continue;
+            }
+            holes.push(hand);
+            let weight = weight * hand_weight as f64;
+            recurse(
+                holdings,
+                index + 1,
+                holes,
+                used | hand,
+                board,
+                weight,
+                tally,
+            );
+            holes.pop();
+        }
+    }
+
+    let known = check_cards(holdings, board, dead)?;
+    let mut tally = Tally::new(holdings.len());
+    let board: CardSet = board.iter().collect();
+    recurse(holdings, 0, &mut Vec::new(), known, board, 1.0, &mut tally);
+    if tally.total == 0 {
+        return Err("Can't deal hands from the ranges without conflicts".into());
+    }
+    Ok(tally.into_equity(true))
+}
+
+/// Calculates the equity of each holding, exactly if there're at most `cfg.exact_threshold`
+/// run-outs, otherwise by sampling `cfg.samples` boards.
+pub fn calculate(
+    holdings: &[Holding],
+    board: &[Card],
+    dead: &[Card],
+    cfg: EquityCfg,
+) -> Result<Equity, String> {
+    let known = check_cards(holdings, board, dead)?;
+    if count_run_outs(holdings, known, board.len()) <= cfg.exact_threshold {
+        exact(holdings, board, dead)
+    } else {
+        monte_carlo(holdings, board, dead, cfg)
+    }
+}
+
+/// fewest boards sampled for a hand of the hero's range in `range_vs_range`
+const MIN_HAND_SAMPLES: usize = 100;
+
+/// Calculates the equity of ranges against each other, and the equity of each hand of the
+/// first range, the hero's. Each hand is calculated like `calculate` in parallel, with the
+/// samples and the exact threshold of `cfg` split between the hands so a wide range takes
+/// about as long as a single hand.
+/// A hand counts in proportion to its weight times the total weight of the villains' hands
+/// it doesn't conflict with, which is exact heads up but ignores the conflicts between villains.
+pub fn range_vs_range(
+    ranges: &[Range],
+    board: &[Card],
+    dead: &[Card],
+    cfg: EquityCfg,
+) -> Result<RangeEquity, String> {
+    let holdings: Vec<Holding> = ranges.iter().cloned().map(Holding::Range).collect();
+    let known = check_cards(&holdings, board, dead)?;
+    let mut hero = ranges[0].clone();
+    hero.remove_conflicts(known);
+    let hero_hands: Vec<([Card; 2], f32)> = hero.iter().collect();
+
+    let results: Vec<(HandEquity, Vec<f64>)> = hero_hands
+        .par_iter()
+        .enumerate()
+        .filter_map(|(index, &(hand, weight))| {
+            let mut holdings = vec![Holding::Hand(hand)];
+            let mut villain_weight = 1.0;
+            for range in &ranges[1..] {
+                let mut range = range.clone();
+                range.remove_conflicts(known | hand.iter().collect());
+                villain_weight *= range.get_total_weight();
+                holdings.push(Holding::Range(range));
+            }
+            // different seeds so hands don't share the same boards
+            let cfg = EquityCfg {
+                samples: (cfg.samples / hero_hands.len()).max(MIN_HAND_SAMPLES),
+                seed: cfg.seed.map(|seed| seed.wrapping_add(index as u64)),
+                exact_threshold: cfg.exact_threshold / hero_hands.len(),
+            };
+            // skip hands that the villains can't play against
+            let equity = calculate(&holdings, board, dead, cfg).ok()?;
+            let hand_equity = HandEquity {
+                hand,
+                weight,
+                equity: equity.get_equity()[0],
+                villain_weight,
+            };
+            Some((hand_equity, equity.get_equity().to_vec()))
+        })
+        .collect();
+
+    let mut equity = vec![0.0; ranges.len()];
+    let mut total = 0.0;
+    for (hand, hand_equities) in &results {
+        let weight = hand.weight as f64 * hand.villain_weight;
+        for (range_equity, hand_equity) in equity.iter_mut().zip(hand_equities) {
+            *range_equity += weight * hand_equity;
+        }
+        total += weight;
+    }
+    if total == 0.0 {
+        return Err("Can't deal hands from the ranges without conflicts".into());
+    }
+    for range_equity in equity.iter_mut() {
+        *range_equity /= total;
+    }
+    Ok(RangeEquity {
+        equity,
+        hands: results.into_iter().map(|(hand, _)| hand).collect(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hand(s: &str) -> Holding {
+        Holding::Hand(Card::parse_list(s).unwrap().try_into().unwrap())
+    }
+
+    #[test]
+    fn monte_carlo_tests() {
+        let cfg = EquityCfg {
+            samples: 20_000,
+            seed: Some(0),
+            ..EquityCfg::default()
+        };
+        // aces vs kings is about 82% to 18%
+        let equity = monte_carlo(&[hand("AsAh"), hand("KsKh")], &[], &[], cfg).unwrap();
+        assert!((equity.get_equity()[0] - 82.0).abs() < 1.5);
+        assert_eq!(equity.get_samples(), 20_000);
+        let total: f64 = equity.get_equity().iter().sum();
+        assert!((total - 100.0).abs() < 1e-6);
+
+        // the board plays a royal flush so everyone ties
+        let board = Card::parse_list("Ts Js Qs Ks As").unwrap();
+        let equity = monte_carlo(&[hand("2c3c"), hand("4d5d")], &board, &[], cfg).unwrap();
+        assert_eq!(equity.get_tie(), [100.0, 100.0]);
+
+        // same seed gives the same result
+        let range = Holding::Range("QQ, 72o:0.5".parse().unwrap());
+        let first = monte_carlo(&[hand("AsAh"), range.clone()], &[], &[], cfg).unwrap();
+        let second = monte_carlo(&[hand("AsAh"), range], &[], &[], cfg).unwrap();
+        assert_eq!(first.get_equity(), second.get_equity());
+
+        assert!(monte_carlo(&[hand("AsAh"), hand("AsKh")], &[], &[], cfg).is_err());
+        assert!(monte_carlo(&[hand("AsAh")], &[], &[], cfg).is_err());
+        let no_samples = EquityCfg { samples: 0, ..cfg };
+        assert!(monte_carlo(&[hand("AsAh"), hand("KsKh")], &[], &[], no_samples).is_err());
+        // 44 dead cards leave 6, not enough for a board and a hand from the range
+        let alive: CardSet = Card::parse_list("As Ah Ks Kh Qs Qh Qd Jd")
+            .unwrap()
+            .into_iter()
+            .collect();
+        let dead: Vec<Card> = (!alive).iter().collect();
+        let range = Holding::Range("KK".parse().unwrap());
+        assert!(monte_carlo(&[hand("AsAh"), range.clone()], &[], &dead, cfg).is_err());
+        assert!(calculate(&[hand("AsAh"), range], &[], &dead, cfg).is_err());
+        // or for a board with two fixed hands
+        assert!(monte_carlo(&[hand("AsAh"), hand("KsKh")], &[], &dead, cfg).is_err());
+    }
+
+    #[test]
+    fn exact_tests() {
+        // heads up all-in on the flop, 990 run-outs
+        let board = Card::parse_list("Kd 7h 2c").unwrap();
+        let holdings = [hand("AsAh"), hand("KsKh")];
+        let equity = exact(&holdings, &board, &[]).unwrap();
+        assert!(equity.is_exact());
+        assert_eq!(equity.get_samples(), 990);
+        // aces need an ace without the last king, 2 * 42 boards, or both aces
+        assert_eq!(equity.get_win_count(), [85.0, 905.0]);
+        assert_eq!(equity.get_tie_count(), [0.0, 0.0]);
+
+        // turn, picked automatically
+        let board = Card::parse_list("Kd 7h 2c 3s").unwrap();
+        let equity = calculate(&holdings, &board, &[], EquityCfg::default()).unwrap();
+        assert!(equity.is_exact());
+        assert_eq!(equity.get_samples(), 44);
+
+        // preflop has too many run-outs
+        let cfg = EquityCfg {
+            samples: 1000,
+            ..EquityCfg::default()
+        };
+        let equity = calculate(&holdings, &[], &[], cfg).unwrap();
+        assert!(!equity.is_exact());
+
+        // kings against aces half the time and queens, 3 combos of aces to 6 of queens
+        let board = Card::parse_list("Jd 8h 2c 3s 9d").unwrap();
+        let range = Holding::Range("AA:0.5, QQ".parse().unwrap());
+        let equity = exact(&[hand("KsKh"), range], &board, &[]).unwrap();
+        assert!((equity.get_equity()[0] - 6.0 / 9.0 * 100.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn range_vs_range_tests() {
+        // 3 combos of aces make a set and beat kings, 6 combos of queens lose
+        let board = Card::parse_list("As 7d 9h Jc 3c").unwrap();
+        let ranges = ["AA, QQ".parse().unwrap(), "KK".parse().unwrap()];
+        let equity = range_vs_range(&ranges, &board, &[], EquityCfg::default()).unwrap();
+        assert_eq!(equity.get_hands().len(), 9);
+        assert!((equity.get_equity()[0] - 100.0 / 3.0).abs() < 1e-9);
+        assert!((equity.get_equity()[1] - 200.0 / 3.0).abs() < 1e-9);
+        let histogram = equity.get_histogram(4);
+        assert!((histogram[0] - 2.0 / 3.0).abs() < 1e-9);
+        assert!((histogram[3] - 1.0 / 3.0).abs() < 1e-9);
+        assert!(equity.get_histogram(0).is_empty());
+        // hands that don't weigh anything don't make NaNs
+        let mut weightless = equity.clone();
+        for hand in weightless.hands.iter_mut() {
+            hand.villain_weight = 0.0;
+        }
+        assert_eq!(weightless.get_histogram(4), [0.0; 4]);
+
+        let ranges = ["AA".parse().unwrap(), "AA".parse().unwrap()];
+        assert!(range_vs_range(&ranges, &board, &[], EquityCfg::default()).is_err());
+    }
+}
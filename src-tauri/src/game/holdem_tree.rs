@@ -0,0 +1,237 @@
+//! Heads-up no-limit hold'em abstracted to be solved with Monte Carlo CFR. Hands are
+//! bucketed by a card abstraction and the bets are the sizes of a betting abstraction
+
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+
+use super::abstraction::{fnv_hash, CardAbstraction, CardAbstractionCfg};
+use super::betting::{AbstractAction, BettingAbstraction, BettingState};
+use super::card::{evaluate, Card, CardSet};
+use super::cfr::{ExtensiveGame, Node};
+use super::BettingRound;
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(default)]
+pub struct HoldemTreeCfg {
+    pub stack: usize, // chips of each player at the start of a hand
+    pub small_blind_amount: usize,
+    pub big_blind_amount: usize,
+    pub betting: BettingAbstraction,
+    pub cards: CardAbstractionCfg,
+}
+impl Default for HoldemTreeCfg {
+    fn default() -> Self {
+        HoldemTreeCfg {
+            stack: 200,
+            small_blind_amount: 1,
+            big_blind_amount: 2,
+            betting: BettingAbstraction::default(),
+            cards: CardAbstractionCfg::default(),
+        }
+    }
+}
+
+/// hash of the parts of the config that the betting of the abstraction depends on
+pub fn get_betting_hash(cfg: &HoldemTreeCfg) -> u64 {
+    let betting = (
+        cfg.stack,
+        cfg.small_blind_amount,
+        cfg.big_blind_amount,
+        &cfg.betting,
+    );
+    fnv_hash(
+        serde_json::to_vec(&betting)
+            .unwrap()
+            .into_iter()
+            .map(u64::from),
+    )
+}
+
+#[derive(Clone, Debug)]
+pub enum HoldemAction {
+    Deal([Card; 9]), // every card of the hand at once, see `HoldemState`
+    Bet(AbstractAction),
+}
+
+#[derive(Clone, Debug)]
+pub struct HoldemState {
+    cards: Option<[Card; 9]>, // the dealer's hole cards, the big blind's, then the community
+    buckets: [[u16; 4]; 2],   // of each player in each betting round
+    betting: BettingState,
+}
+
+/// the abstracted game, player 0 is the dealer
+pub struct HoldemTree {
+    cfg: HoldemTreeCfg,
+    cards: CardAbstraction,
+}
+impl HoldemTree {
+    /// loads the bucket tables of the card abstraction
+    pub fn new(cfg: HoldemTreeCfg) -> Result<HoldemTree, String> {
+        BettingState::new(cfg.stack, cfg.small_blind_amount, cfg.big_blind_amount)?;
+        Ok(HoldemTree {
+            cards: CardAbstraction::new(&cfg.cards)?,
+            cfg,
+        })
+    }
+    pub fn get_cfg(&self) -> &HoldemTreeCfg {
+        &self.cfg
+    }
+    pub fn get_card_abstraction(&self) -> &CardAbstraction {
+        &self.cards
+    }
+    /// betting at the start of a hand
+    pub fn new_betting(&self) -> BettingState {
+        BettingState::new(
+            self.cfg.stack,
+            self.cfg.small_blind_amount,
+            self.cfg.big_blind_amount,
+        )
+        .unwrap() // checked in `new`
+    }
+    /// info set of a player with the bucket of their hand, the bucket (u16, little endian)
+    /// followed by the history of the betting
+    pub fn get_key(bucket: u16, betting: &BettingState) -> Vec<u8> {
+        [&bucket.to_le_bytes()[..], betting.get_history()].concat()
+    }
+}
+impl ExtensiveGame for HoldemTree {
+    type State = HoldemState;
+    type Action = HoldemAction;
+    type InfoSet = Vec<u8>;
+
+    fn get_root(&self) -> HoldemState {
+        HoldemState {
+            cards: None,
+            buckets: [[0; 4]; 2],
+            betting: self.new_betting(),
+        }
+    }
+    fn get_node(&self, state: &HoldemState) -> Node {
+        if state.cards.is_none() {
+            return Node::Chance;
+        }
+        match state.betting.get_player() {
+            Some(player) => Node::Player(player),
+            None => Node::Terminal,
+        }
+    }
+    fn get_actions(&self, state: &HoldemState) -> Vec<HoldemAction> {
+        state
+            .betting
+            .get_actions(&self.cfg.betting)
+            .into_iter()
+            .map(HoldemAction::Bet)
+            .collect()
+    }
+    /// there're too many deals to list, they're sampled with `sample_chance`
+    fn get_chance_outcomes(&self, _state: &HoldemState) -> Vec<(HoldemAction, f64)> {
+        Vec::new()
+    }
+    fn sample_chance<R: Rng>(&self, _state: &HoldemState, rng: &mut R) -> HoldemAction {
+        let mut deck = CardSet::FULL;
+        HoldemAction::Deal([(); 9].map(|_| deck.draw(rng).unwrap()))
+    }
+    fn get_info_set(&self, state: &HoldemState) -> Vec<u8> {
+        let player = state.betting.get_player().unwrap();
+        let bucket = state.buckets[player][state.betting.get_betting_round() as usize];
+        HoldemTree::get_key(bucket, &state.betting)
+    }
+    fn apply(&self, state: &HoldemState, action: &HoldemAction) -> HoldemState {
+        let mut next = state.clone();
+        match action {
+            HoldemAction::Deal(cards) => {
+                // bucket the hands of every round now, the states after the deal share them
+                let rounds = [
+                    BettingRound::PreFlop,
+                    BettingRound::Flop,
+                    BettingRound::Turn,
+                    BettingRound::River,
+                ];
+                for player in 0..2 {
+                    let hole = &cards[2 * player..2 * player + 2];
+                    for round in rounds {
+                        next.buckets[player][round as usize] =
+                            self.cards.get_bucket(round, hole, &cards[4..]);
+                    }
+                }
+                next.cards = Some(*cards);
+            }
+            HoldemAction::Bet(action) => {
+                next.betting = state.betting.apply(&self.cfg.betting, *action)
+            }
+        }
+        next
+    }
+    /// in big blinds
+    fn get_payoff(&self, state: &HoldemState) -> f64 {
+        let betting = &state.betting;
+        let won = match betting.get_folded() {
+            // the player who folded loses what they put in
+            Some(0) => -(betting.get_put_in(0) as f64),
+            Some(_) => betting.get_put_in(1) as f64,
+            None => {
+                let cards = state.cards.unwrap();
+                let strength = |player: usize| {
+                    evaluate(
+                        cards[2 * player..2 * player + 2]
+                            .iter()
+                            .chain(&cards[4..])
+                            .collect(),
+                    )
+                };
+                // an all-in for less only wins what the player put in
+                let called = betting.get_put_in(0).min(betting.get_put_in(1)) as f64;
+                match strength(0).cmp(&strength(1)) {
+                    std::cmp::Ordering::Greater => called,
+                    std::cmp::Ordering::Less => -called,
+                    std::cmp::Ordering::Equal => 0.0,
+                }
+            }
+        };
+        won / self.cfg.big_blind_amount as f64
+    }
+    /// the betting and the buckets of the card abstraction
+    fn get_hash(&self) -> u64 {
+        fnv_hash([get_betting_hash(&self.cfg), self.cards.get_hash()])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::mccfr::{Mccfr, MccfrCfg};
+    use super::*;
+
+    #[test]
+    fn abstract_hands() {
+        let tree = HoldemTree::new(HoldemTreeCfg::default()).unwrap();
+        let cards: Vec<Card> = Card::parse_list("As Ah 7c 2d Ad Kd 9s 3h 4c").unwrap();
+        let deal = HoldemAction::Deal(cards.try_into().unwrap());
+        let state = tree.apply(&tree.get_root(), &deal);
+        assert_eq!(tree.get_node(&state), Node::Player(0));
+        // pocket aces are the first hand class, then hit a set in the top strength bucket
+        assert_eq!(tree.get_info_set(&state), [0, 0]);
+        assert_eq!(state.buckets[0][1], 9);
+
+        // the big blind folds to a raise and loses the blind
+        let raise = HoldemAction::Bet(AbstractAction::Bet(0));
+        let state = tree.apply(&state, &raise);
+        let fold = HoldemAction::Bet(AbstractAction::Fold);
+        let folded = tree.apply(&state, &fold);
+        assert_eq!(tree.get_node(&folded), Node::Terminal);
+        assert_eq!(tree.get_payoff(&folded), 1.0);
+        // all-in and called, the aces win the big blind's stack
+        let all_in = tree.apply(&state, &HoldemAction::Bet(AbstractAction::AllIn));
+        let called = tree.apply(&all_in, &HoldemAction::Bet(AbstractAction::Call));
+        assert_eq!(tree.get_payoff(&called), 100.0);
+
+        // a short run of Monte Carlo CFR reaches info sets of every player
+        let mut mccfr = Mccfr::new(tree, MccfrCfg::default()).unwrap();
+        mccfr.iterate(100);
+        let strategies = mccfr.get_average_strategies();
+        assert!(strategies.len() > 100);
+        for (_, strategy) in strategies {
+            assert!((strategy.iter().sum::<f64>() - 1.0).abs() < 1e-9);
+        }
+    }
+}
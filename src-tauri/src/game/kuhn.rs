@@ -0,0 +1,161 @@
+//! Kuhn poker, small enough to check the solvers against its known equilibrium.
+//! Both players ante 1 and get one of J, Q and K, then they can bet 1 once.
+//! The first player acts first and loses 1/18 per hand at equilibrium
+
+use serde::{Deserialize, Serialize};
+
+use super::card::Card;
+use super::cfr::{ExtensiveGame, Node};
+use super::small_game::{SmallGame, SmallGameView};
+
+/// J, Q and K
+const NUM_RANK: u8 = 3;
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum KuhnAction {
+    Deal(u8), // rank of the card dealt to the next player, 0 is J
+    Pass,     // check, or fold to a bet
+    Bet,      // bet, or call a bet
+}
+
+#[derive(Serialize, Deserialize, Clone, Default, Debug)]
+pub struct KuhnState {
+    cards: Vec<u8>, // rank of each player's card
+    history: Vec<KuhnAction>,
+}
+
+pub struct Kuhn;
+impl Kuhn {
+    /// payoff of the first player at equilibrium
+    pub const GAME_VALUE: f64 = -1.0 / 18.0;
+
+    fn get_card(rank: u8) -> Card {
+        ["Js", "Qs", "Ks"][rank as usize].parse().unwrap()
+    }
+}
+impl ExtensiveGame for Kuhn {
+    type State = KuhnState;
+    type Action = KuhnAction;
+    type InfoSet = String;
+
+    fn get_root(&self) -> KuhnState {
+        KuhnState::default()
+    }
+    fn get_node(&self, state: &KuhnState) -> Node {
+        use KuhnAction::*;
+        if state.cards.len() < 2 {
+            return Node::Chance;
+        }
+        match state.history[..] {
+            [Pass, Pass] | [Bet, _] | [Pass, Bet, _] => Node::Terminal,
+            _ => Node::Player(state.history.len() % 2),
+        }
+    }
+    fn get_actions(&self, _state: &KuhnState) -> Vec<KuhnAction> {
+        vec![KuhnAction::Pass, KuhnAction::Bet]
+    }
+    fn get_chance_outcomes(&self, state: &KuhnState) -> Vec<(KuhnAction, f64)> {
+        let remaining = NUM_RANK as usize - state.cards.len();
+        (0..NUM_RANK)
+            .filter(|rank| !state.cards.contains(rank))
+            .map(|rank| (KuhnAction::Deal(rank), 1.0 / remaining as f64))
+            .collect()
+    }
+    /// the player's card then the actions, eg. Kpb
+    fn get_info_set(&self, state: &KuhnState) -> String {
+        let player = state.history.len() % 2;
+        let mut info_set = String::from(["J", "Q", "K"][state.cards[player] as usize]);
+        for action in state.history.iter() {
+            info_set.push(match action {
+                KuhnAction::Pass => 'p',
+                _ => 'b',
+            });
+        }
+        info_set
+    }
+    fn apply(&self, state: &KuhnState, action: &KuhnAction) -> KuhnState {
+        let mut next = state.clone();
+        match *action {
+            KuhnAction::Deal(rank) => next.cards.push(rank),
+            action => next.history.push(action),
+        }
+        next
+    }
+    fn get_payoff(&self, state: &KuhnState) -> f64 {
+        use KuhnAction::*;
+        let showdown = if state.cards[0] > state.cards[1] {
+            1.0
+        } else {
+            -1.0
+        };
+        match state.history[..] {
+            [Pass, Pass] => showdown,
+            [Bet, Pass] => 1.0,
+            [Pass, Bet, Pass] => -1.0,
+            _ => 2.0 * showdown,
+        }
+    }
+}
+impl SmallGame for Kuhn {
+    fn get_view(&self, state: &KuhnState, person_index: usize) -> SmallGameView {
+        let terminal = self.get_node(state) == Node::Terminal;
+        let mut put_in = vec![1.0, 1.0];
+        let mut history = Vec::new();
+        let mut bet = false;
+        for (index, action) in state.history.iter().enumerate() {
+            history.push(match (action, bet) {
+                (KuhnAction::Pass, false) => "Check",
+                (KuhnAction::Pass, true) => "Fold",
+                (_, false) => "Bet",
+                (_, true) => "Call",
+            });
+            if *action == KuhnAction::Bet {
+                put_in[index % 2] += 1.0;
+                bet = true;
+            }
+        }
+        let actions = if terminal || state.cards.len() < 2 {
+            Vec::new()
+        } else if bet {
+            vec!["Fold", "Call"]
+        } else {
+            vec!["Check", "Bet"]
+        };
+        let sign = if person_index == 0 { 1.0 } else { -1.0 };
+        SmallGameView {
+            person_index,
+            holes: state
+                .cards
+                .iter()
+                .map(|&rank| Some(Kuhn::get_card(rank)))
+                .collect(),
+            community: Vec::new(),
+            history: history.into_iter().map(String::from).collect(),
+            actions: actions.into_iter().map(String::from).collect(),
+            put_in,
+            showdown: terminal && !(bet && state.history.last() == Some(&KuhnAction::Pass)),
+            payoff: terminal.then(|| sign * self.get_payoff(state)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::cfr::{Cfr, CfrVariant};
+    use super::*;
+
+    #[test]
+    fn equilibrium() {
+        let mut cfr = Cfr::new(Kuhn, CfrVariant::CfrPlus);
+        cfr.iterate(2_000);
+        assert_eq!(cfr.get_info_sets().len(), 12);
+        assert!((cfr.get_value() - Kuhn::GAME_VALUE).abs() < 1e-3);
+        assert!(cfr.get_exploitability() < 1e-3);
+        // the second player bets a king when checked to and never calls a bet with a jack
+        let strategy = |info_set: &str| cfr.get_average_strategy(&info_set.into()).unwrap();
+        assert!(strategy("Kp")[1] > 0.99);
+        assert!(strategy("Jb")[0] > 0.99);
+        // the first player calls a bet with a queen a third more often than it bets a jack
+        assert!((strategy("Qpb")[1] - strategy("J")[1] - 1.0 / 3.0).abs() < 0.02);
+    }
+}
@@ -0,0 +1,265 @@
+//! Leduc hold'em, a two round game with the structure of hold'em. The deck has two each of
+//! J, Q and K. Both players ante 1 and get one card, then a community card is dealt after
+//! the first round. Bets are 2 in the first round and 4 in the second, with at most a bet
+//! and a raise each round. A pair with the community card wins, otherwise the higher card
+
+use serde::{Deserialize, Serialize};
+
+use super::card::Card;
+use super::cfr::{ExtensiveGame, Node};
+use super::small_game::{SmallGame, SmallGameView};
+
+/// J, Q and K
+const NUM_RANK: u8 = 3;
+/// bet and raise size of each round
+const BET_SIZES: [f64; 2] = [2.0, 4.0];
+const MAX_RAISES: usize = 2;
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum LeducAction {
+    Deal(u8), // rank of the card dealt, 0 is J
+    Fold,
+    Call,  // checks if there's no bet
+    Raise, // bets if there's no bet
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct LeducState {
+    cards: Vec<u8>, // rank of each player's card then the community card
+    rounds: Vec<Vec<LeducAction>>,
+}
+
+pub struct Leduc;
+impl Leduc {
+    /// payoff of the first player at equilibrium
+    pub const GAME_VALUE: f64 = -0.085606;
+
+    /// the two cards of a rank are a spade and a heart, in the order they're dealt
+    fn get_cards(state: &LeducState) -> Vec<Card> {
+        state
+            .cards
+            .iter()
+            .enumerate()
+            .map(|(index, &rank)| {
+                let suit = if state.cards[..index].contains(&rank) {
+                    "h"
+                } else {
+                    "s"
+                };
+                format!("{}{}", ["J", "Q", "K"][rank as usize], suit)
+                    .parse()
+                    .unwrap()
+            })
+            .collect()
+    }
+
+    /// chips each player has put in the pot
+    fn get_put_in(state: &LeducState) -> [f64; 2] {
+        let mut put_in = [1.0, 1.0];
+        for (round, actions) in state.rounds.iter().enumerate() {
+            for (index, action) in actions.iter().enumerate() {
+                let player = index % 2;
+                match action {
+                    LeducAction::Call => put_in[player] = put_in[1 - player],
+                    LeducAction::Raise => put_in[player] = put_in[1 - player] + BET_SIZES[round],
+                    _ => {}
+                }
+            }
+        }
+        put_in
+    }
+
+    /// what an action is called, `bet` is whether there's a bet to call
+    fn get_action_name(action: LeducAction, bet: bool) -> &'static str {
+        match (action, bet) {
+            (LeducAction::Call, false) => "Check",
+            (LeducAction::Call, true) => "Call",
+            (LeducAction::Raise, false) => "Bet",
+            (LeducAction::Raise, true) => "Raise",
+            _ => "Fold",
+        }
+    }
+
+    /// true if both players checked or a bet was called
+    fn is_round_over(actions: &[LeducAction]) -> bool {
+        actions.len() >= 2 && actions.last() == Some(&LeducAction::Call)
+    }
+
+    /// rank of the player's hand, pairs are above every card
+    fn get_hand_rank(state: &LeducState, player: usize) -> u8 {
+        if state.cards[player] == state.cards[2] {
+            NUM_RANK + state.cards[player]
+        } else {
+            state.cards[player]
+        }
+    }
+}
+impl ExtensiveGame for Leduc {
+    type State = LeducState;
+    type Action = LeducAction;
+    type InfoSet = String;
+
+    fn get_root(&self) -> LeducState {
+        LeducState {
+            cards: Vec::new(),
+            rounds: vec![Vec::new()],
+        }
+    }
+    fn get_node(&self, state: &LeducState) -> Node {
+        let actions = state.rounds.last().unwrap();
+        if state.cards.len() < 2 {
+            Node::Chance
+        } else if actions.last() == Some(&LeducAction::Fold) {
+            Node::Terminal
+        } else if Leduc::is_round_over(actions) {
+            if state.cards.len() < 3 {
+                Node::Chance
+            } else {
+                Node::Terminal
+            }
+        } else {
+            Node::Player(actions.len() % 2)
+        }
+    }
+    fn get_actions(&self, state: &LeducState) -> Vec<LeducAction> {
+        let put_in = Leduc::get_put_in(state);
+        let raises = state
+            .rounds
+            .last()
+            .unwrap()
+            .iter()
+            .filter(|&&action| action == LeducAction::Raise)
+            .count();
+        let mut actions = Vec::new();
+        // folding without a bet to call is never better than checking
+        if put_in[0] != put_in[1] {
+            actions.push(LeducAction::Fold);
+        }
+        actions.push(LeducAction::Call);
+        if raises < MAX_RAISES {
+            actions.push(LeducAction::Raise);
+        }
+        actions
+    }
+    /// by rank, each rank has two cards
+    fn get_chance_outcomes(&self, state: &LeducState) -> Vec<(LeducAction, f64)> {
+        let remaining = (2 * NUM_RANK) as usize - state.cards.len();
+        (0..NUM_RANK)
+            .map(|rank| {
+                let dealt = state.cards.iter().filter(|&&card| card == rank).count();
+                (
+                    LeducAction::Deal(rank),
+                    (2 - dealt) as f64 / remaining as f64,
+                )
+            })
+            .filter(|&(_, probability)| probability > 0.0)
+            .collect()
+    }
+    /// the player's card, the community card then the actions of each round, eg. KQ:rc/r
+    fn get_info_set(&self, state: &LeducState) -> String {
+        let player = state.rounds.last().unwrap().len() % 2;
+        let rank = |rank: u8| ["J", "Q", "K"][rank as usize];
+        let mut info_set = String::from(rank(state.cards[player]));
+        if let Some(&community) = state.cards.get(2) {
+            info_set.push_str(rank(community));
+        }
+        info_set.push(':');
+        for (round, actions) in state.rounds.iter().enumerate() {
+            if round > 0 {
+                info_set.push('/');
+            }
+            for action in actions.iter() {
+                info_set.push(match action {
+                    LeducAction::Fold => 'f',
+                    LeducAction::Call => 'c',
+                    _ => 'r',
+                });
+            }
+        }
+        info_set
+    }
+    fn apply(&self, state: &LeducState, action: &LeducAction) -> LeducState {
+        let mut next = state.clone();
+        match *action {
+            LeducAction::Deal(rank) => {
+                next.cards.push(rank);
+                // the community card starts the second round
+                if next.cards.len() == 3 {
+                    next.rounds.push(Vec::new());
+                }
+            }
+            action => next.rounds.last_mut().unwrap().push(action),
+        }
+        next
+    }
+    fn get_payoff(&self, state: &LeducState) -> f64 {
+        let put_in = Leduc::get_put_in(state);
+        let actions = state.rounds.last().unwrap();
+        if actions.last() == Some(&LeducAction::Fold) {
+            // the player who folded loses what they put in
+            return if actions.len() % 2 == 1 {
+                -put_in[0]
+            } else {
+                put_in[1]
+            };
+        }
+        match Leduc::get_hand_rank(state, 0).cmp(&Leduc::get_hand_rank(state, 1)) {
+            std::cmp::Ordering::Greater => put_in[1],
+            std::cmp::Ordering::Less => -put_in[0],
+            std::cmp::Ordering::Equal => 0.0,
+        }
+    }
+}
+impl SmallGame for Leduc {
+    fn get_view(&self, state: &LeducState, person_index: usize) -> SmallGameView {
+        let node = self.get_node(state);
+        let mut history = Vec::new();
+        for actions in state.rounds.iter() {
+            let mut bet = false;
+            for action in actions.iter() {
+                history.push(Leduc::get_action_name(*action, bet));
+                bet = bet || *action == LeducAction::Raise;
+            }
+        }
+        let actions = match node {
+            Node::Player(_) => {
+                let put_in = Leduc::get_put_in(state);
+                let bet = put_in[0] != put_in[1];
+                self.get_actions(state)
+                    .iter()
+                    .map(|&action| Leduc::get_action_name(action, bet))
+                    .collect()
+            }
+            _ => Vec::new(),
+        };
+        let mut cards = Leduc::get_cards(state);
+        let community = cards.split_off(cards.len().min(2));
+        let terminal = node == Node::Terminal;
+        let sign = if person_index == 0 { 1.0 } else { -1.0 };
+        SmallGameView {
+            person_index,
+            holes: cards.into_iter().map(Some).collect(),
+            community,
+            history: history.into_iter().map(String::from).collect(),
+            actions: actions.into_iter().map(String::from).collect(),
+            put_in: Leduc::get_put_in(state).to_vec(),
+            showdown: terminal && state.rounds.last().unwrap().last() != Some(&LeducAction::Fold),
+            payoff: terminal.then(|| sign * self.get_payoff(state)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::cfr::{Cfr, CfrVariant};
+    use super::*;
+
+    #[test]
+    fn equilibrium() {
+        let mut cfr = Cfr::new(Leduc, CfrVariant::default());
+        cfr.iterate(200);
+        assert_eq!(cfr.get_info_sets().len(), 288);
+        assert!((cfr.get_value() - Leduc::GAME_VALUE).abs() < 1e-3);
+        assert!(cfr.get_exploitability() < 0.005);
+    }
+}
@@ -0,0 +1,537 @@
+//! Monte Carlo CFR, samples the cards and some of the actions every iteration instead of
+//! traversing the whole tree, for games too big for `Cfr` like abstracted hold'em.
+//! Iterations run in parallel on a table of info sets shared by the threads
+
+use std::{
+    collections::{hash_map::DefaultHasher, HashMap},
+    fs::{self, File},
+    hash::{Hash, Hasher},
+    io::{BufReader, BufWriter, Read, Write},
+    path::Path,
+    sync::Mutex,
+};
+
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use super::cfr::{normalize, ExtensiveGame, Node};
+
+/// start of a checkpoint file
+const MAGIC: &[u8; 4] = b"MCCF";
+/// version of the checkpoint file, bumped when the format changes
+const CHECKPOINT_VERSION: u32 = 2;
+/// actions an info set of a checkpoint can have, so a corrupt file can't allocate too much
+const MAX_ACTIONS: usize = 255;
+/// locks the info sets are split between, so the threads rarely wait for each other
+const NUM_SHARD: usize = 256;
+
+/// info sets that can be written to a file
+pub trait InfoSetKey: Clone + Eq + Hash + Send + Sync {
+    fn to_bytes(&self) -> Vec<u8>;
+    fn from_bytes(bytes: &[u8]) -> Result<Self, String>;
+}
+impl InfoSetKey for String {
+    fn to_bytes(&self) -> Vec<u8> {
+        self.as_bytes().to_vec()
+    }
+    fn from_bytes(bytes: &[u8]) -> Result<String, String> {
+        String::from_utf8(bytes.to_vec()).map_err(|e| e.to_string())
+    }
+}
+impl InfoSetKey for Vec<u8> {
+    fn to_bytes(&self) -> Vec<u8> {
+        self.clone()
+    }
+    fn from_bytes(bytes: &[u8]) -> Result<Vec<u8>, String> {
+        Ok(bytes.to_vec())
+    }
+}
+
+/// what's sampled each iteration
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Debug)]
+pub enum Sampling {
+    // samples chance and the opponent's actions, tries every action of the updated player
+    External,
+    // samples a single history, the updated player picks a uniformly random action
+    // with probability `exploration`
+    Outcome { exploration: f64 },
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(default)]
+pub struct MccfrCfg {
+    pub sampling: Sampling,
+    pub linear: bool,   // weight iteration t by t, like linear CFR
+    pub threads: usize, // 0 for one per core
+    pub seed: u64,      // iteration t samples with seed + t
+}
+impl Default for MccfrCfg {
+    fn default() -> Self {
+        MccfrCfg {
+            sampling: Sampling::External,
+            linear: true,
+            threads: 0,
+            seed: 0,
+        }
+    }
+}
+
+/// accumulated regrets and strategies of an info set
+#[derive(Clone, Debug)]
+struct Regrets {
+    regrets: Vec<f64>,
+    strategy_sum: Vec<f64>,
+}
+impl Regrets {
+    fn get_strategy(&self) -> Vec<f64> {
+        normalize(self.regrets.iter().map(|&regret| regret.max(0.0)).collect())
+    }
+}
+
+/// picks an index with probability in proportion to its weight
+fn sample<R: Rng>(weights: &[f64], rng: &mut R) -> usize {
+    let mut target = rng.gen::<f64>() * weights.iter().sum::<f64>();
+    for (index, weight) in weights.iter().enumerate() {
+        if target < *weight {
+            return index;
+        }
+        target -= weight;
+    }
+    weights.len() - 1
+}
+
+/// Solves a game with Monte Carlo CFR, each iteration updates the first player then the second.
+/// with more than one thread the iterations aren't repeatable
+pub struct Mccfr<G: ExtensiveGame> {
+    game: G,
+    cfg: MccfrCfg,
+    shards: Vec<Mutex<HashMap<G::InfoSet, Regrets>>>,
+    iterations: usize,
+    pool: rayon::ThreadPool,
+}
+impl<G> Mccfr<G>
+where
+    G: ExtensiveGame + Sync,
+    G::InfoSet: InfoSetKey,
+{
+    pub fn new(game: G, cfg: MccfrCfg) -> Result<Mccfr<G>, String> {
+        let pool = rayon::ThreadPoolBuilder::new()
+            .num_threads(cfg.threads)
+            .build()
+            .map_err(|e| format!("Can't start the threads: {}", e))?;
+        Ok(Mccfr {
+            game,
+            cfg,
+            shards: (0..NUM_SHARD).map(|_| Mutex::new(HashMap::new())).collect(),
+            iterations: 0,
+            pool,
+        })
+    }
+    pub fn get_game(&self) -> &G {
+        &self.game
+    }
+    pub fn get_iterations(&self) -> usize {
+        self.iterations
+    }
+    pub fn get_num_info_sets(&self) -> usize {
+        self.shards
+            .iter()
+            .map(|shard| shard.lock().unwrap().len())
+            .sum()
+    }
+
+    fn get_shard(&self, info_set: &G::InfoSet) -> &Mutex<HashMap<G::InfoSet, Regrets>> {
+        let mut hasher = DefaultHasher::new();
+        info_set.hash(&mut hasher);
+        &self.shards[hasher.finish() as usize % NUM_SHARD]
+    }
+    /// current strategy at an info set, by regret matching
+    fn get_strategy(&self, info_set: &G::InfoSet, num_action: usize) -> Vec<f64> {
+        self.get_shard(info_set)
+            .lock()
+            .unwrap()
+            .entry(info_set.clone())
+            .or_insert_with(|| Regrets {
+                regrets: vec![0.0; num_action],
+                strategy_sum: vec![0.0; num_action],
+            })
+            .get_strategy()
+    }
+    fn update<F: FnOnce(&mut Regrets)>(&self, info_set: &G::InfoSet, f: F) {
+        if let Some(regrets) = self.get_shard(info_set).lock().unwrap().get_mut(info_set) {
+            f(regrets);
+        }
+    }
+
+    /// average strategy at an info set, None if it hasn't been reached
+    pub fn get_average_strategy(&self, info_set: &G::InfoSet) -> Option<Vec<f64>> {
+        self.get_shard(info_set)
+            .lock()
+            .unwrap()
+            .get(info_set)
+            .map(|regrets| normalize(regrets.strategy_sum.clone()))
+    }
+    /// average strategy of every info set reached
+    pub fn get_average_strategies(&self) -> Vec<(G::InfoSet, Vec<f64>)> {
+        self.shards
+            .iter()
+            .flat_map(|shard| {
+                shard
+                    .lock()
+                    .unwrap()
+                    .iter()
+                    .map(|(info_set, regrets)| {
+                        (info_set.clone(), normalize(regrets.strategy_sum.clone()))
+                    })
+                    .collect::<Vec<_>>()
+            })
+            .collect()
+    }
+    /// sum of the largest positive regret at each info set divided by the weight of the
+    /// iterations, goes to 0 as the average strategy gets closer to an equilibrium
+    pub fn get_average_regret(&self) -> f64 {
+        let total: f64 = self
+            .shards
+            .iter()
+            .map(|shard| {
+                shard
+                    .lock()
+                    .unwrap()
+                    .values()
+                    .map(|node| node.regrets.iter().fold(0.0, |max: f64, &r| max.max(r)))
+                    .sum::<f64>()
+            })
+            .sum();
+        let t = self.iterations as f64;
+        let weight = if self.cfg.linear {
+            t * (t + 1.0) / 2.0
+        } else {
+            t
+        };
+        total / weight.max(1.0)
+    }
+
+    /// runs the iterations in parallel
+    pub fn iterate(&mut self, iterations: usize) {
+        let start = self.iterations + 1;
+        let this = &*self;
+        self.pool.install(|| {
+            (start..start + iterations).into_par_iter().for_each(|t| {
+                let mut rng = StdRng::seed_from_u64(this.cfg.seed.wrapping_add(t as u64));
+                let weight = if this.cfg.linear { t as f64 } else { 1.0 };
+                for player in 0..2 {
+                    let root = this.game.get_root();
+                    match this.cfg.sampling {
+                        Sampling::External => {
+                            this.external(&root, player, weight, &mut rng);
+                        }
+                        Sampling::Outcome { exploration } => {
+                            let reaches = [1.0; 3];
+                            this.outcome(&root, player, reaches, exploration, weight, &mut rng);
+                        }
+                    }
+                }
+            });
+        });
+        self.iterations += iterations;
+    }
+
+    /// updates the regrets of `player` trying all of their actions,
+    /// returns the sampled payoff of `player`
+    fn external(&self, state: &G::State, player: usize, weight: f64, rng: &mut StdRng) -> f64 {
+        let acting = match self.game.get_node(state) {
+            Node::Terminal => {
+                let sign = if player == 0 { 1.0 } else { -1.0 };
+                return sign * self.game.get_payoff(state);
+            }
+            Node::Chance => {
+                let outcome = self.game.sample_chance(state, rng);
+                return self.external(&self.game.apply(state, &outcome), player, weight, rng);
+            }
+            Node::Player(acting) => acting,
+        };
+        let actions = self.game.get_actions(state);
+        let info_set = self.game.get_info_set(state);
+        let strategy = self.get_strategy(&info_set, actions.len());
+        if acting != player {
+            // the opponent's average strategy is updated when it's sampled
+            self.update(&info_set, |node| {
+                for (sum, probability) in node.strategy_sum.iter_mut().zip(&strategy) {
+                    *sum += weight * probability;
+                }
+            });
+            let action = &actions[sample(&strategy, rng)];
+            return self.external(&self.game.apply(state, action), player, weight, rng);
+        }
+        let values: Vec<f64> = actions
+            .iter()
+            .map(|action| self.external(&self.game.apply(state, action), player, weight, rng))
+            .collect();
+        let value: f64 = values.iter().zip(&strategy).map(|(v, p)| v * p).sum();
+        self.update(&info_set, |node| {
+            for (regret, action_value) in node.regrets.iter_mut().zip(&values) {
+                *regret += weight * (action_value - value);
+            }
+        });
+        value
+    }
+
+    /// Updates the regrets of `player` along one sampled history. `reaches` are the player's,
+    /// the opponent's and the sampling probability of the state. returns the payoff of
+    /// `player` divided by the sampling probability of the history, and the probability
+    /// of getting from the state to the end of the history
+    fn outcome(
+        &self,
+        state: &G::State,
+        player: usize,
+        reaches: [f64; 3],
+        exploration: f64,
+        weight: f64,
+        rng: &mut StdRng,
+    ) -> (f64, f64) {
+        let [reach, others_reach, sample_reach] = reaches;
+        let acting = match self.game.get_node(state) {
+            Node::Terminal => {
+                let sign = if player == 0 { 1.0 } else { -1.0 };
+                return (sign * self.game.get_payoff(state) / sample_reach, 1.0);
+            }
+            // sampled by its probability, which cancels out in the reach and the sampling
+            Node::Chance => {
+                let outcome = self.game.sample_chance(state, rng);
+                let next = self.game.apply(state, &outcome);
+                return self.outcome(&next, player, reaches, exploration, weight, rng);
+            }
+            Node::Player(acting) => acting,
+        };
+        let actions = self.game.get_actions(state);
+        let info_set = self.game.get_info_set(state);
+        let strategy = self.get_strategy(&info_set, actions.len());
+        let policy: Vec<f64> = if acting == player {
+            let uniform = 1.0 / actions.len() as f64;
+            strategy
+                .iter()
+                .map(|p| exploration * uniform + (1.0 - exploration) * p)
+                .collect()
+        } else {
+            strategy.clone()
+        };
+        let index = sample(&policy, rng);
+        let next_reaches = if acting == player {
+            [
+                reach * strategy[index],
+                others_reach,
+                sample_reach * policy[index],
+            ]
+        } else {
+            [
+                reach,
+                others_reach * strategy[index],
+                sample_reach * policy[index],
+            ]
+        };
+        let next = self.game.apply(state, &actions[index]);
+        let (utility, tail) = self.outcome(&next, player, next_reaches, exploration, weight, rng);
+        if acting == player {
+            let counterfactual = utility * others_reach;
+            self.update(&info_set, |node| {
+                for (other, regret) in node.regrets.iter_mut().enumerate() {
+                    *regret += weight
+                        * if other == index {
+                            counterfactual * tail * (1.0 - strategy[index])
+                        } else {
+                            -counterfactual * tail * strategy[index]
+                        };
+                }
+                for (sum, probability) in node.strategy_sum.iter_mut().zip(&strategy) {
+                    *sum += weight * reach / sample_reach * probability;
+                }
+            });
+        }
+        (utility, tail * strategy[index])
+    }
+
+    /// Writes the regrets to a temporary file then renames it, so an interrupted save keeps
+    /// the previous checkpoint. the format is "MCCF", version (u32), hash of the game (u64),
+    /// see `ExtensiveGame::get_hash`, iterations (u64), number of info sets (u64), then for each info set the length of its key (u32),
+    /// the key, the number of actions (u32), the regrets and the strategy sums (f64).
+    /// all little endian
+    pub fn save(&self, path: &Path) -> Result<(), String> {
+        let temporary = path.with_extension("tmp");
+        let write = || -> std::io::Result<()> {
+            let mut writer = BufWriter::new(File::create(&temporary)?);
+            writer.write_all(MAGIC)?;
+            writer.write_all(&CHECKPOINT_VERSION.to_le_bytes())?;
+            writer.write_all(&self.game.get_hash().to_le_bytes())?;
+            writer.write_all(&(self.iterations as u64).to_le_bytes())?;
+            writer.write_all(&(self.get_num_info_sets() as u64).to_le_bytes())?;
+            for shard in self.shards.iter() {
+                for (info_set, node) in shard.lock().unwrap().iter() {
+                    let key = info_set.to_bytes();
+                    writer.write_all(&(key.len() as u32).to_le_bytes())?;
+                    writer.write_all(&key)?;
+                    writer.write_all(&(node.regrets.len() as u32).to_le_bytes())?;
+                    for value in node.regrets.iter().chain(&node.strategy_sum) {
+                        writer.write_all(&value.to_le_bytes())?;
+                    }
+                }
+            }
+            writer.flush()?;
+            fs::rename(&temporary, path)
+        };
+        write().map_err(|e| format!("Can't write {:?}: {}", path, e))
+    }
+
+    /// continues from a checkpoint written by `save` for the same game
+    pub fn load(game: G, cfg: MccfrCfg, path: &Path) -> Result<Mccfr<G>, String> {
+        let mut reader =
+            BufReader::new(File::open(path).map_err(|e| format!("Can't read {:?}: {}", path, e))?);
+        let mut read = |num_byte: usize| -> Result<Vec<u8>, String> {
+            let mut bytes = vec![0; num_byte];
+            reader
+                .read_exact(&mut bytes)
+                .map_err(|e| format!("Can't read {:?}: {}", path, e))?;
+            Ok(bytes)
+        };
+        if read(4)? != MAGIC {
+            return Err(format!("{:?} isn't a checkpoint", path));
+        }
+        let version = u32::from_le_bytes(read(4)?.try_into().unwrap());
+        if version != CHECKPOINT_VERSION {
+            return Err(format!(
+                "{:?} is version {}, expected {}",
+                path, version, CHECKPOINT_VERSION
+            ));
+        }
+        let hash = u64::from_le_bytes(read(8)?.try_into().unwrap());
+        if hash != game.get_hash() {
+            return Err(format!("{:?} is a checkpoint of another game", path));
+        }
+        let mut mccfr = Mccfr::new(game, cfg)?;
+        mccfr.iterations = u64::from_le_bytes(read(8)?.try_into().unwrap()) as usize;
+        let num_info_set = u64::from_le_bytes(read(8)?.try_into().unwrap());
+        for _ in 0..num_info_set {
+            let key_len = u32::from_le_bytes(read(4)?.try_into().unwrap()) as usize;
+            let info_set = G::InfoSet::from_bytes(&read(key_len)?)?;
+            let num_action = u32::from_le_bytes(read(4)?.try_into().unwrap()) as usize;
+            let num_byte = num_action
+                .checked_mul(2 * 8)
+                .filter(|_| num_action <= MAX_ACTIONS)
+                .ok_or(format!(
+                    "{:?} has an info set of {} actions",
+                    path, num_action
+                ))?;
+            let values: Vec<f64> = read(num_byte)?
+                .chunks_exact(8)
+                .map(|bytes| f64::from_le_bytes(bytes.try_into().unwrap()))
+                .collect();
+            let regrets = Regrets {
+                regrets: values[..num_action].to_vec(),
+                strategy_sum: values[num_action..].to_vec(),
+            };
+            mccfr
+                .get_shard(&info_set)
+                .lock()
+                .unwrap()
+                .insert(info_set, regrets);
+        }
+        Ok(mccfr)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::cfr::get_exploitability;
+    use super::super::holdem_tree::{HoldemTree, HoldemTreeCfg};
+    use super::super::kuhn::Kuhn;
+    use super::super::leduc::Leduc;
+    use super::*;
+
+    fn exploitability<G>(mccfr: &Mccfr<G>) -> f64
+    where
+        G: ExtensiveGame + Sync,
+        G::InfoSet: InfoSetKey,
+    {
+        get_exploitability(mccfr.get_game(), &|info_set| {
+            mccfr.get_average_strategy(info_set)
+        })
+    }
+
+    #[test]
+    fn converges_to_equilibrium() {
+        // outcome sampling has more variance, so it's further from equilibrium
+        for (sampling, tolerance) in [
+            (Sampling::External, 0.01),
+            (Sampling::Outcome { exploration: 0.6 }, 0.06),
+        ] {
+            let cfg = MccfrCfg {
+                sampling,
+                ..MccfrCfg::default()
+            };
+            let mut mccfr = Mccfr::new(Kuhn, cfg.clone()).unwrap();
+            mccfr.iterate(20_000);
+            assert_eq!(mccfr.get_num_info_sets(), 12);
+            assert!(exploitability(&mccfr) < tolerance, "{:?}", sampling);
+
+            let mut mccfr = Mccfr::new(Leduc, cfg).unwrap();
+            mccfr.iterate(100);
+            let first_exploitability = exploitability(&mccfr);
+            mccfr.iterate(4_900);
+            assert!(exploitability(&mccfr) < first_exploitability / 2.0);
+        }
+    }
+
+    #[test]
+    fn checkpoints() {
+        let cfg = MccfrCfg {
+            threads: 1,
+            ..MccfrCfg::default()
+        };
+        let path = std::env::temp_dir().join("mccfr_checkpoint_test.bin");
+        let mut mccfr = Mccfr::new(Leduc, cfg.clone()).unwrap();
+        mccfr.iterate(100);
+        mccfr.save(&path).unwrap();
+        let mut resumed = Mccfr::load(Leduc, cfg, &path).unwrap();
+        fs::remove_file(&path).unwrap();
+        assert_eq!(resumed.get_iterations(), 100);
+        assert_eq!(resumed.get_num_info_sets(), mccfr.get_num_info_sets());
+        // resuming on one thread picks up where it left off
+        mccfr.iterate(100);
+        resumed.iterate(100);
+        for (info_set, strategy) in mccfr.get_average_strategies() {
+            assert_eq!(resumed.get_average_strategy(&info_set).unwrap(), strategy);
+        }
+        assert!(Mccfr::load(Leduc, MccfrCfg::default(), &path).is_err());
+
+        // the number of actions of an info set is bounded
+        mccfr.save(&path).unwrap();
+        let mut bytes = fs::read(&path).unwrap();
+        let key_len = u32::from_le_bytes(bytes[32..36].try_into().unwrap()) as usize;
+        for num_action in [256, u32::MAX] {
+            bytes[36 + key_len..40 + key_len].copy_from_slice(&num_action.to_le_bytes());
+            fs::write(&path, &bytes).unwrap();
+            assert!(Mccfr::load(Leduc, MccfrCfg::default(), &path).is_err());
+        }
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn checkpoints_of_another_game() {
+        let path = std::env::temp_dir().join("mccfr_holdem_checkpoint_test.bin");
+        let tree = HoldemTree::new(HoldemTreeCfg::default()).unwrap();
+        let mut mccfr = Mccfr::new(tree, MccfrCfg::default()).unwrap();
+        mccfr.iterate(10);
+        mccfr.save(&path).unwrap();
+        let load = |cfg| Mccfr::load(HoldemTree::new(cfg).unwrap(), MccfrCfg::default(), &path);
+        assert!(load(HoldemTreeCfg::default()).is_ok());
+        // other betting or other buckets
+        let mut cfg = HoldemTreeCfg::default();
+        cfg.stack += 100;
+        assert!(load(cfg).is_err());
+        let mut cfg = HoldemTreeCfg::default();
+        cfg.cards.num_strength_bucket += 1;
+        assert!(load(cfg).is_err());
+        assert!(Mccfr::load(Leduc, MccfrCfg::default(), &path).is_err());
+        fs::remove_file(&path).unwrap();
+    }
+}
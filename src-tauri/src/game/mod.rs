@@ -1,11 +1,77 @@
-mod card;
+pub mod abstraction;
+pub mod agent;
+pub mod arena;
+pub mod betting;
+pub mod blueprint;
+pub mod bots;
+pub mod card;
+pub mod cfr;
+pub mod equity;
+pub mod holdem_tree;
+pub mod kuhn;
+pub mod leduc;
+pub mod mccfr;
+pub mod outs;
+pub mod preflop;
+pub mod small_game;
 
 use std::{cmp::min, ops::RangeInclusive};
 
-use self::card::{Card, Deck, Hand};
+use self::abstraction::BucketTable;
+use self::bots::BotCfg;
+use self::card::{evaluate, BestHand, Card, Deck, HandStrength, HandType, Range};
+use self::equity::{Equity, EquityCfg, Holding};
+use self::outs::{Outs, OutsTarget};
 use itertools::Itertools;
 use serde::{Deserialize, Serialize};
 
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Variant {
+    #[default]
+    NoLimitHoldem,
+    LimitHoldem,
+    Pineapple,      // 3 hole cards, discard one before the flop
+    CrazyPineapple, // 3 hole cards, discard one after the flop
+}
+impl Variant {
+    fn get_num_hole_cards(self) -> usize {
+        match self {
+            Variant::NoLimitHoldem | Variant::LimitHoldem => 2,
+            Variant::Pineapple | Variant::CrazyPineapple => 3,
+        }
+    }
+    /// returns the betting round that players discard after, None if there's no discarding
+    fn get_discard_round(self) -> Option<BettingRound> {
+        match self {
+            Variant::NoLimitHoldem | Variant::LimitHoldem => None,
+            Variant::Pineapple => Some(BettingRound::PreFlop),
+            Variant::CrazyPineapple => Some(BettingRound::Flop),
+        }
+    }
+}
+
+/// when the session switches to the next game in the rotation
+#[derive(Serialize, Deserialize, Debug, Clone, Copy)]
+pub enum Schedule {
+    EveryHands(usize), // switch after this many hands
+    PerOrbit,          // switch every time the button goes around the table, like HORSE
+    DealersChoice,     // the dealer picks the game for the next orbit, bots keep the current one
+}
+
+/// a game in the rotation with its own stakes
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct RotationGame {
+    variant: Variant,
+    small_blind_amount: usize,
+    big_blind_amount: usize,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Rotation {
+    games: Vec<RotationGame>,
+    schedule: Schedule,
+}
+
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct GameCfg {
     player_name: [String; 2],
@@ -13,28 +79,64 @@ pub struct GameCfg {
     small_blind_amount: usize,
     big_blind_amount: usize,
     first_dealer_index: usize,
+    #[serde(default)]
+    variant: Variant,
+    // None when only playing `variant`
+    #[serde(default)]
+    rotation: Option<Rotation>,
+    // bot playing each seat, None for seats played by a person
+    #[serde(default)]
+    bots: [Option<BotCfg>; 2],
+    // milliseconds the bots wait before acting in the app
+    #[serde(default)]
+    think_delay: u64,
+    // deals the n-th hand from a deck shuffled with seed + n, random if None.
+    // read from the config but never written, like the deck's order
+    #[serde(default, skip_serializing)]
+    seed: Option<u64>,
+}
+impl GameCfg {
+    /// returns the variant and blinds of the game at `game_index` of the rotation.
+    /// falls back to the single game if there's no rotation
+    fn get_stakes(&self, game_index: usize) -> (Variant, usize, usize) {
+        match &self.rotation {
+            Some(rotation) => {
+                let game = &rotation.games[game_index];
+                (game.variant, game.small_blind_amount, game.big_blind_amount)
+            }
+            None => (self.variant, self.small_blind_amount, self.big_blind_amount),
+        }
+    }
+    /// deck of the hand after `hands_played` hands
+    fn new_deck(&self, hands_played: usize) -> Deck {
+        match self.seed {
+            Some(seed) => Deck::shuffled(seed.wrapping_add(hands_played as u64)),
+            None => Deck::new(),
+        }
+    }
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug)]
 struct Player {
     name: String,
-    hole: [Card; 2],
+    hole: Vec<Card>, // 2 cards, or 3 before discarding in pineapple
     stack: usize,
     bet_size: usize,
     folded: bool,
 }
 
-#[derive(Serialize, Deserialize)]
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Debug)]
 pub enum Action {
     Call,
     Bet(usize),
     Raise(usize),
     Check,
     Fold,
+    Discard(usize), // index of the hole card to discard
 }
 
-#[derive(Serialize, Deserialize, Clone)]
-enum BettingRound {
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum BettingRound {
     PreFlop,
     Flop,
     Turn,
@@ -51,8 +153,78 @@ impl BettingRound {
             BettingRound::River => BettingRound::PreFlop,
         }
     }
+    /// community cards dealt by this round
+    pub fn get_num_community(self) -> usize {
+        match self {
+            BettingRound::PreFlop => 0,
+            BettingRound::Flop => 3,
+            BettingRound::Turn => 4,
+            BettingRound::River => 5,
+        }
+    }
+}
+
+/// an action of the current hand, with the pot it was taken against
+#[derive(Serialize, Deserialize, Clone, PartialEq, Eq, Debug)]
+pub struct ActionRecord {
+    betting_round: BettingRound,
+    player_index: usize,
+    action: Action,
+    pot: usize,     // chips in the pot and the bets before the action
+    to_call: usize, // chips the player had to put in to call
+}
+impl ActionRecord {
+    pub fn get_betting_round(&self) -> BettingRound {
+        self.betting_round
+    }
+    pub fn get_player_index(&self) -> usize {
+        self.player_index
+    }
+    pub fn get_action(&self) -> Action {
+        self.action
+    }
+    pub fn get_pot(&self) -> usize {
+        self.pot
+    }
+    pub fn get_to_call(&self) -> usize {
+        self.to_call
+    }
 }
 
+/// hands shown at the end of a hand
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct Showdown {
+    hands: Vec<Option<BestHand>>, // best hand of each player, None if folded
+    winners_indices: Vec<usize>,
+}
+
+/// a player as the people at the table see them
+#[derive(Serialize, Clone, Debug)]
+pub struct PlayerView {
+    name: String,
+    hole: Vec<Option<Card>>, // None for the cards of a bot
+    stack: usize,
+    bet_size: usize,
+    folded: bool,
+}
+
+/// The game as the people at the table see it, what the app is sent. the bots' hole cards
+/// are hidden, the showdown shows them, and the deck isn't there.
+/// it can't be deserialized, the app plays the game it keeps
+#[derive(Serialize, Clone, Debug)]
+pub struct GameView {
+    players: Vec<PlayerView>,
+    bots: Vec<bool>, // seats played by a bot
+    community: Vec<Card>,
+    dealer_index: usize,
+    pot_size: usize,
+    current_player_index: usize,
+    discarding: bool,
+    last_showdown: Option<Showdown>,
+    hands_played: usize,
+    session_over: bool,
+    history: Vec<ActionRecord>,
+}
 #[derive(Serialize, Deserialize, Clone)]
 pub struct Game {
     cfg: GameCfg,
@@ -66,6 +238,17 @@ pub struct Game {
     min_raise: usize,
     current_player_index: usize,
     previous_active_index: Option<usize>, // last person to bet/raise, None when game starts
+    game_index: usize,                    // index of the current game in the rotation
+    hands_in_game: usize,                 // hands played since switching to current game
+    chosen_game_index: Option<usize>,     // game picked by the dealer for dealer's choice
+    discarding: bool,                     // players are discarding a hole card instead of betting
+    last_showdown: Option<Showdown>,      // None if the previous hand didn't go to showdown
+    #[serde(default)]
+    hands_played: usize, // hands finished since the start of the session
+    #[serde(default)]
+    session_over: bool, // a player couldn't pay the blinds of the next hand
+    #[serde(default)]
+    history: Vec<ActionRecord>, // actions of the current hand
 }
 impl Game {
     const NUM_PLAYER: usize = 2;
@@ -123,18 +306,28 @@ impl Game {
     /// make new game based on cfg
     /// returns error when cfg's don't have enough for blinds
     pub fn new(cfg: GameCfg) -> Result<Game, String> {
+        // check the rotation
+        if let Some(rotation) = &cfg.rotation {
+            if rotation.games.is_empty() {
+                return Err("Rotation doesn't have any games".into());
+            }
+            if let Schedule::EveryHands(0) = rotation.schedule {
+                return Err("Rotation has to play at least one hand per game".into());
+            }
+        }
+        let (variant, small_blind_amount, big_blind_amount) = cfg.get_stakes(0);
         // find the play order
         let dealer_index = cfg.first_dealer_index;
         let small_blind_index = Game::get_small_blind_index(dealer_index);
         let current_player_index = Game::get_first_player_index(true, dealer_index);
 
         // create decks and players
-        let mut deck = Deck::new();
+        let mut deck = cfg.new_deck(0);
         let mut players = Vec::with_capacity(Game::NUM_PLAYER);
         for i in 0..Game::NUM_PLAYER {
             players.push(Player {
                 name: cfg.player_name[i].clone(),
-                hole: [deck.random_card(), deck.random_card()],
+                hole: deck.deal(variant.get_num_hole_cards()),
                 stack: cfg.starting_chip[i],
                 bet_size: 0,
                 folded: false,
@@ -144,12 +337,12 @@ impl Game {
         Game::assign_blinds(
             &mut players,
             small_blind_index,
-            cfg.small_blind_amount,
+            small_blind_amount,
             (small_blind_index + 1) % Game::NUM_PLAYER,
-            cfg.big_blind_amount,
+            big_blind_amount,
         )?;
         // minimum raise at start of game is big blind
-        let min_raise = cfg.big_blind_amount;
+        let min_raise = big_blind_amount;
         // return the new game
         Ok(Game {
             cfg,
@@ -163,9 +356,120 @@ impl Game {
             min_raise,
             current_player_index,
             previous_active_index: None, // no raise at start of game, BB doesn't count
+            game_index: 0,
+            hands_in_game: 0,
+            chosen_game_index: None,
+            discarding: false,
+            last_showdown: None,
+            hands_played: 0,
+            session_over: false,
+            history: Vec::new(),
         })
     }
 
+    pub fn get_dealer_index(&self) -> usize {
+        self.dealer_index
+    }
+    /// true if a bot of the config plays the seat
+    pub fn is_bot_seat(&self, seat_index: usize) -> bool {
+        matches!(self.cfg.bots.get(seat_index), Some(Some(_)))
+    }
+    /// seat of the person at the table, the current player if it's a person,
+    /// otherwise the first seat that isn't a bot. None if the bots play every seat
+    pub fn get_person_index(&self) -> Option<usize> {
+        if !self.is_bot_seat(self.current_player_index) {
+            return Some(self.current_player_index);
+        }
+        (0..Game::NUM_PLAYER).find(|&index| !self.is_bot_seat(index))
+    }
+    /// the game without what the people can't see, the bots' cards and the deck
+    pub fn get_view(&self) -> GameView {
+        GameView {
+            players: self
+                .players
+                .iter()
+                .enumerate()
+                .map(|(index, player)| PlayerView {
+                    name: player.name.clone(),
+                    hole: player
+                        .hole
+                        .iter()
+                        .map(|&card| (!self.is_bot_seat(index)).then_some(card))
+                        .collect(),
+                    stack: player.stack,
+                    bet_size: player.bet_size,
+                    folded: player.folded,
+                })
+                .collect(),
+            bots: (0..Game::NUM_PLAYER)
+                .map(|index| self.is_bot_seat(index))
+                .collect(),
+            community: self.community.clone(),
+            dealer_index: self.dealer_index,
+            pot_size: self.pot_size,
+            current_player_index: self.current_player_index,
+            discarding: self.discarding,
+            last_showdown: self.last_showdown.clone(),
+            hands_played: self.hands_played,
+            session_over: self.session_over,
+            history: self.history.clone(),
+        }
+    }
+    pub fn get_variant(&self) -> Variant {
+        self.cfg.get_stakes(self.game_index).0
+    }
+
+    fn get_big_blind_amount(&self) -> usize {
+        self.cfg.get_stakes(self.game_index).2
+    }
+
+    /// returns the index of the game in the rotation to switch to after this hand.
+    /// returns None if the current game carries on
+    fn get_next_game_index(&self) -> Option<usize> {
+        let rotation = self.cfg.rotation.as_ref()?;
+        // hands_in_game doesn't include the hand that just finished
+        let hands_played = self.hands_in_game + 1;
+        match rotation.schedule {
+            Schedule::EveryHands(num_hands) if hands_played >= num_hands => {
+                Some((self.game_index + 1) % rotation.games.len())
+            }
+            Schedule::PerOrbit if hands_played >= Game::NUM_PLAYER => {
+                Some((self.game_index + 1) % rotation.games.len())
+            }
+            Schedule::DealersChoice if hands_played >= Game::NUM_PLAYER => {
+                // keep playing the same game if the dealer didn't choose
+                Some(self.chosen_game_index.unwrap_or(self.game_index))
+            }
+            _ => None,
+        }
+    }
+
+    /// let the dealer choose the game of the next orbit in dealer's choice.
+    /// returns error if not playing dealer's choice, `seat_index` isn't the dealer's
+    /// or the index is not in the rotation
+    pub fn choose_next_game(&self, seat_index: usize, game_index: usize) -> Result<Game, String> {
+        if seat_index != self.dealer_index {
+            return Err(format!(
+                "Only the dealer in seat {} can choose",
+                self.dealer_index
+            ));
+        }
+        match &self.cfg.rotation {
+            Some(Rotation {
+                games,
+                schedule: Schedule::DealersChoice,
+            }) => {
+                if game_index >= games.len() {
+                    return Err(format!("{} is not a game in the rotation", game_index));
+                }
+                let mut new_game = self.clone();
+                new_game.chosen_game_index = Some(game_index);
+                Ok(new_game)
+            }
+            _ => Err("Not playing dealer's choice".into()),
+        }
+    }
+
     fn get_previous_player_index(&self) -> usize {
         if self.current_player_index != 0 {
             self.current_player_index - 1
@@ -198,99 +502,133 @@ impl Game {
             self.players[*index].stack += self.pot_size / winners_indices.len();
         }
         // TODO: decides who gets the leftover chip
+        // pick the next game in the rotation, stacks carry across
+        let (game_index, hands_in_game, chosen_game_index) = match self.get_next_game_index() {
+            // start of a new game, or a new orbit of dealer's choice
+            Some(game_index) => (game_index, 0, None),
+            None => (
+                self.game_index,
+                self.hands_in_game + 1,
+                self.chosen_game_index,
+            ),
+        };
+        let (variant, small_blind_amount, big_blind_amount) = self.cfg.get_stakes(game_index);
         // rotates button
         let dealer_index = (self.dealer_index + 1) % Game::NUM_PLAYER;
         let small_blind_index = Game::get_small_blind_index(dealer_index);
         let mut players = self.players.clone();
-        // assign blinds
-        Game::assign_blinds(
+        // assign blinds, the session is over if a player can't pay theirs
+        let session_over = Game::assign_blinds(
             &mut players,
             small_blind_index,
-            self.cfg.small_blind_amount,
+            small_blind_amount,
             (small_blind_index + 1) % Game::NUM_PLAYER,
-            self.cfg.big_blind_amount,
+            big_blind_amount,
         )
-        .unwrap(); // panics if players don't have enough stack
+        .is_err();
+        if session_over {
+            players = self.players.clone();
+        }
 
-        // reset folded
-        for mut player in players.iter_mut() {
+        // reset folded and deal new hole cards
+        let mut deck = self.cfg.new_deck(self.hands_played + 1);
+        for player in players.iter_mut() {
             player.folded = false;
+            player.hole = deck.deal(variant.get_num_hole_cards());
         }
         // assign self to new game
         *self = Game {
             cfg: self.cfg.clone(),
-            deck: Deck::new(),
+            deck,
             players,
             community: Vec::new(),
             dealer_index,
             small_blind_index,
             betting_round: BettingRound::PreFlop,
             pot_size: 0,
-            min_raise: self.cfg.big_blind_amount,
+            min_raise: big_blind_amount,
             current_player_index: Game::get_first_player_index(true, dealer_index),
             previous_active_index: None,
+            game_index,
+            hands_in_game,
+            chosen_game_index,
+            discarding: false,
+            last_showdown: None,
+            hands_played: self.hands_played + 1,
+            session_over,
+            history: Vec::new(),
         };
     }
 
     pub fn next_betting_round(&mut self) {
+        let big_blind_amount = self.get_big_blind_amount();
+        let discard_round = self.get_variant().get_discard_round();
+        let next_discarding_player_index = self.get_next_discarding_player_index();
         let mut deal_cards_to_community = |num_card: usize| {
             for _ in 0..num_card {
                 self.community.push(self.deck.random_card());
             }
         };
 
-        /// return the winning player indices after comparing hands
-        fn showdown(game: &Game) -> Vec<usize> {
-            // convert community to array
-            // also double check to see if community is full
-            let community_array: [Card; 5] = game
-                .community
-                .clone()
-                .try_into()
-                .unwrap_or_else(|v: Vec<Card>| panic!("Community not full ({}/5)", v.len()));
-            // find best hand of all players
-            let best_hands: Vec<Hand> = game
+        /// compare the hands of the remaining players and find the winners
+        fn showdown(game: &Game) -> Showdown {
+            // find best hand of all players, the community doesn't have to be full
+            let strengths: Vec<Option<HandStrength>> = game
                 .players
                 .iter()
                 .map(|player| {
-                    Hand::get_all_hands(player.hole, community_array)
-                        .into_iter()
-                        .max()
-                        .unwrap()
+                    if player.folded {
+                        return None;
+                    }
+                    // players should have discarded down to 2 cards by now
+                    if player.hole.len() != 2 {
+                        panic!("{} has {} hole cards", player.name, player.hole.len());
+                    }
+                    Some(evaluate(
+                        player.hole.iter().chain(&game.community).collect(),
+                    ))
                 })
                 .collect();
             // get all the winners
-            let first_hand = best_hands[0];
-            let (winning_hand, winning_players_indices) = best_hands.into_iter().enumerate().fold(
-                (first_hand, Vec::new()),
-                |(max_hand, mut indices), (index, hand)| {
-                    if hand > max_hand {
-                        return (hand, vec![index]);
-                    }
-                    if hand == max_hand {
-                        indices.push(index);
-                    }
-                    return (max_hand, indices);
-                },
-            );
-            println!(
-                "Winner: {}",
-                winning_players_indices
-                    .iter()
-                    .map(|i| game.players[*i].name.clone())
-                    .join(", ")
-            );
-            println!("Hand type: {}", winning_hand.get_hand_type());
-            winning_players_indices
+            let winning_strength = strengths.iter().max().copied().flatten();
+            let winners_indices: Vec<usize> = strengths
+                .iter()
+                .positions(|&strength| strength == winning_strength)
+                .collect();
+            Showdown {
+                hands: game.find_made_hands(),
+                winners_indices,
+            }
         }
 
         // reset min_raise
-        self.min_raise = self.cfg.big_blind_amount;
+        self.min_raise = big_blind_amount;
+        // return the part of a bet that nobody could call
+        for index in 0..Game::NUM_PLAYER {
+            let called = (0..Game::NUM_PLAYER)
+                .filter(|&other| other != index)
+                .map(|other| self.players[other].bet_size)
+                .max()
+                .unwrap_or(0);
+            let player = &mut self.players[index];
+            if player.bet_size > called {
+                player.stack += player.bet_size - called;
+                player.bet_size = called;
+            }
+        }
         // add up and reset bets
         for player in self.players.iter_mut() {
             self.pot_size += player.bet_size;
             player.bet_size = 0;
         }
+        // players discard before the next cards are dealt in pineapple
+        if discard_round == Some(self.betting_round) {
+            if let Some(index) = next_discarding_player_index {
+                self.discarding = true;
+                self.current_player_index = index;
+                return; // deal the cards after everyone discarded
+            }
+        }
         // starting player
         self.current_player_index = Game::get_first_player_index(false, self.dealer_index);
         self.previous_active_index = None;
@@ -300,17 +638,151 @@ impl Game {
             BettingRound::Flop => deal_cards_to_community(1),
             BettingRound::Turn => deal_cards_to_community(1),
             BettingRound::River => {
-                let winners_indices = showdown(self);
-                // go to next game
-                self.go_to_next_game(winners_indices);
+                let showdown = showdown(self);
+                // go to next game, keeping the showdown so it can be shown
+                self.go_to_next_game(showdown.winners_indices.clone());
+                self.last_showdown = Some(showdown);
                 return; // skip the self.betting_round.next()
             }
         }
         self.betting_round.next();
     }
 
+    /// returns the index of the first remaining player who hasn't discarded
+    fn get_next_discarding_player_index(&self) -> Option<usize> {
+        let num_hole_cards = self.get_variant().get_num_hole_cards();
+        self.players
+            .iter()
+            .position(|player| !player.folded && player.hole.len() == num_hole_cards)
+    }
+
+    /// calculate the equity of the players who haven't folded, in order of their index.
+    /// hole cards of folded players are dead
+    pub fn get_equity(&self, cfg: EquityCfg) -> Result<Equity, String> {
+        let mut holdings = Vec::new();
+        let mut dead = Vec::new();
+        for player in self.players.iter() {
+            if player.folded {
+                dead.extend(player.hole.iter().copied());
+                continue;
+            }
+            match player.hole.clone().try_into() {
+                Ok(hole) => holdings.push(Holding::Hand(hole)),
+                Err(_) => return Err(format!("{} hasn't discarded yet", player.name)),
+            }
+        }
+        equity::calculate(&holdings, &self.community, &dead, cfg)
+    }
+
+    /// equity of a seat as its player sees it, against a random hand for each player who
+    /// hasn't folded. the seat is first in the equity, the other players' cards aren't used.
+    /// returns error for a bot's seat, its cards are hidden
+    pub fn get_seat_equity(&self, seat_index: usize, cfg: EquityCfg) -> Result<Equity, String> {
+        let player = self.get_person(seat_index)?;
+        if player.folded {
+            return Err(format!("{} has folded", player.name));
+        }
+        let hole = player
+            .hole
+            .clone()
+            .try_into()
+            .map_err(|_| format!("{} hasn't discarded yet", player.name))?;
+        let num_opponent = self.players.iter().filter(|player| !player.folded).count() - 1;
+        let holdings: Vec<Holding> = std::iter::once(Holding::Hand(hole))
+            .chain((0..num_opponent).map(|_| Holding::Range(Range::full())))
+            .collect();
+        equity::calculate(&holdings, &self.community, &[], cfg)
+    }
+
+    /// the hand each player makes with the current community, None if folded
+    fn find_made_hands(&self) -> Vec<Option<BestHand>> {
+        self.players
+            .iter()
+            .map(|player| (!player.folded).then(|| BestHand::find(&player.hole, &self.community)))
+            .collect()
+    }
+    /// the hand each player makes with the current community, None if folded or played by
+    /// a bot. the bots' hands are in the showdown
+    pub fn get_made_hands(&self) -> Vec<Option<BestHand>> {
+        let mut made_hands = self.find_made_hands();
+        for (index, made_hand) in made_hands.iter_mut().enumerate() {
+            if self.is_bot_seat(index) {
+                *made_hand = None;
+            }
+        }
+        made_hands
+    }
+
+    /// outs of a player on the flop or turn to `target`, or to get ahead of a random hand
+    /// if there's no target. the other players' cards aren't used.
+    /// returns error for a bot's seat, its cards are hidden
+    pub fn get_outs(&self, player_index: usize, target: Option<HandType>) -> Result<Outs, String> {
+        let player = self.get_person(player_index)?;
+        let target = match target {
+            Some(hand_type) => OutsTarget::HandType(hand_type),
+            None => OutsTarget::Range(Range::full()),
+        };
+        outs::get_outs(&player.hole, &self.community, &target)
+    }
+
+    /// the player in a seat that isn't played by a bot
+    fn get_person(&self, seat_index: usize) -> Result<&Player, String> {
+        if self.is_bot_seat(seat_index) {
+            return Err(format!("Seat {} is played by a bot", seat_index));
+        }
+        self.players
+            .get(seat_index)
+            .ok_or(format!("No player at index {}", seat_index))
+    }
+
+    /// bucket of a player's hand in a table built for the current betting round
+    pub fn get_bucket(&self, player_index: usize, table: &BucketTable) -> Result<u16, String> {
+        let player = self
+            .players
+            .get(player_index)
+            .ok_or(format!("No player at index {}", player_index))?;
+        if table.get_betting_round() != self.betting_round {
+            return Err(format!(
+                "Bucket table is for the {:?}, not the {:?}",
+                table.get_betting_round(),
+                self.betting_round
+            ));
+        }
+        if player.hole.len() != 2 {
+            return Err(format!(
+                "{} has {} hole cards",
+                player.name,
+                player.hole.len()
+            ));
+        }
+        Ok(table.get_bucket(&player.hole, &self.community))
+    }
+
+    pub fn is_session_over(&self) -> bool {
+        self.session_over
+    }
+
+    pub fn get_history(&self) -> &[ActionRecord] {
+        &self.history
+    }
+
+    /// hands finished in the session and actions of the current hand.
+    /// it goes up with every action, the newer of two games of a session has the larger one
+    pub fn get_progress(&self) -> (usize, usize) {
+        (self.hands_played, self.history.len())
+    }
+
     /// return possible actions for current player
     pub fn get_possible_actions(&self) -> Vec<Action> {
+        if self.session_over {
+            return Vec::new();
+        }
+        if self.discarding {
+            // can only discard one of the hole cards
+            return (0..self.players[self.current_player_index].hole.len())
+                .map(Action::Discard)
+                .collect();
+        }
         if let Some(previous_active_index) = self.previous_active_index {
             if previous_active_index == self.current_player_index {
                 // went back to original raiser, round should end, no possible actions
@@ -355,6 +827,9 @@ impl Game {
             }
             // checking
             possible_actions.push(Action::Check);
+        } else {
+            // the previous player called all-in for less, nothing to call
+            possible_actions.push(Action::Check);
         }
 
         possible_actions
@@ -391,7 +866,22 @@ impl Game {
         let current_player = &self.players[self.current_player_index];
         let previous_bet = self.get_previous_bet();
         let max_amount = current_player.bet_size + current_player.stack - previous_bet;
-        let range = min(max_amount, self.min_raise)..=max_amount;
+        let range = match self.get_variant() {
+            Variant::NoLimitHoldem | Variant::Pineapple | Variant::CrazyPineapple => {
+                min(max_amount, self.min_raise)..=max_amount
+            }
+            Variant::LimitHoldem => {
+                // fixed bet sizes, small bet before the turn and big bet after
+                // heads up so there's no cap on the number of raises
+                let big_blind_amount = self.get_big_blind_amount();
+                let fixed_amount = match self.betting_round {
+                    BettingRound::PreFlop | BettingRound::Flop => big_blind_amount,
+                    BettingRound::Turn | BettingRound::River => big_blind_amount * 2,
+                };
+                let amount = min(max_amount, fixed_amount);
+                amount..=amount
+            }
+        };
         Some(range)
     }
 
@@ -495,8 +985,47 @@ impl Game {
             }
             check(new_game)
         }
+        fn discard(new_game: &mut Game, index: usize) -> Result<(), String> {
+            let current_player = &mut new_game.players[new_game.current_player_index];
+            if index >= current_player.hole.len() {
+                return Err(format!(
+                    "{} doesn't have hole card {}",
+                    current_player.name, index
+                ));
+            }
+            current_player.hole.remove(index);
+
+            match new_game.get_next_discarding_player_index() {
+                Some(index) => new_game.current_player_index = index,
+                None => {
+                    // everyone discarded, deal the next cards
+                    new_game.discarding = false;
+                    new_game.next_betting_round();
+                }
+            }
+            Ok(())
+        }
+        if self.session_over {
+            return Err("The session is over".into());
+        }
+        // can only discard while discarding, and only bet while betting
+        if self.discarding != matches!(action, Action::Discard(_)) {
+            return if self.discarding {
+                Err("Player has to discard a hole card".into())
+            } else {
+                Err("Player can't discard at this point".into())
+            };
+        }
         // create new game
         let mut new_game = self.clone();
+        let bets: usize = self.players.iter().map(|player| player.bet_size).sum();
+        new_game.history.push(ActionRecord {
+            betting_round: self.betting_round,
+            player_index: self.current_player_index,
+            action,
+            pot: self.pot_size + bets,
+            to_call: self.get_call_amount().unwrap_or(0),
+        });
         // execute depends on action
         let result = match action {
             Action::Call => call(&mut new_game),
@@ -504,6 +1033,7 @@ impl Game {
             Action::Raise(amount) => bet_or_raise(&mut new_game, false, amount),
             Action::Check => check(&mut new_game),
             Action::Fold => fold(&mut new_game),
+            Action::Discard(index) => discard(&mut new_game, index),
         };
         match result {
             Ok(_) => Ok(new_game),
@@ -511,3 +1041,229 @@ impl Game {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// heads up game with 100 chips each, the first player deals first
+    fn new_game(blinds: (usize, usize), variant: &str, rotation: &str) -> Game {
+        let cfg = format!(
+            r#"{{
+                "player_name": ["A", "B"],
+                "starting_chip": [100, 100],
+                "small_blind_amount": {},
+                "big_blind_amount": {},
+                "first_dealer_index": 0,
+                "variant": "{}",
+                "rotation": {}
+            }}"#,
+            blinds.0, blinds.1, variant, rotation
+        );
+        Game::new(serde_json::from_str(&cfg).unwrap()).unwrap()
+    }
+
+    /// variant and posted blinds of the hand being played
+    fn get_stakes(game: &Game) -> (Variant, usize, usize) {
+        let small_blind = game.players[game.small_blind_index].bet_size;
+        let big_blind = game.players[(game.small_blind_index + 1) % 2].bet_size;
+        (game.get_variant(), small_blind, big_blind)
+    }
+
+    #[test]
+    fn rotate_games() {
+        let games = r#"[
+            {"variant": "NoLimitHoldem", "small_blind_amount": 1, "big_blind_amount": 2},
+            {"variant": "LimitHoldem", "small_blind_amount": 5, "big_blind_amount": 10},
+            {"variant": "Pineapple", "small_blind_amount": 2, "big_blind_amount": 4}
+        ]"#;
+        // the dealer acts first before the flop and folds every hand
+        let rotation = format!(r#"{{"games": {}, "schedule": {{"EveryHands": 3}}}}"#, games);
+        let mut game = new_game((1, 2), "NoLimitHoldem", &rotation);
+        let mut stakes = Vec::new();
+        for _ in 0..7 {
+            stakes.push(get_stakes(&game));
+            game = game.act(Action::Fold).unwrap();
+        }
+        let nlh = (Variant::NoLimitHoldem, 1, 2);
+        let lhe = (Variant::LimitHoldem, 5, 10);
+        let pineapple = (Variant::Pineapple, 2, 4);
+        assert_eq!(stakes, [nlh, nlh, nlh, lhe, lhe, lhe, pineapple]);
+        assert_eq!(game.cfg.get_stakes(game.game_index), pineapple);
+        assert!(game.choose_next_game(game.dealer_index, 0).is_err());
+
+        // an orbit is a hand on each button heads up
+        let rotation = format!(r#"{{"games": {}, "schedule": "PerOrbit"}}"#, games);
+        let mut game = new_game((1, 2), "NoLimitHoldem", &rotation);
+        let mut stakes = Vec::new();
+        for _ in 0..7 {
+            stakes.push(get_stakes(&game));
+            game = game.act(Action::Fold).unwrap();
+        }
+        assert_eq!(stakes, [nlh, nlh, lhe, lhe, pineapple, pineapple, nlh]);
+
+        // the same game carries on until the dealer chooses another
+        let rotation = format!(r#"{{"games": {}, "schedule": "DealersChoice"}}"#, games);
+        let mut game = new_game((1, 2), "NoLimitHoldem", &rotation);
+        let mut stakes = Vec::new();
+        for hand in 0..6 {
+            stakes.push(get_stakes(&game));
+            if hand == 2 {
+                assert!(game.choose_next_game(0, 3).is_err());
+                // only the dealer chooses
+                assert!(game.choose_next_game(1, 2).is_err());
+                game = game.choose_next_game(0, 2).unwrap();
+            }
+            game = game.act(Action::Fold).unwrap();
+        }
+        assert_eq!(stakes, [nlh, nlh, nlh, nlh, pineapple, pineapple]);
+
+        // a player who can't pay the blinds of the next game ends the session
+        let games = r#"[
+            {"variant": "NoLimitHoldem", "small_blind_amount": 1, "big_blind_amount": 2},
+            {"variant": "NoLimitHoldem", "small_blind_amount": 100, "big_blind_amount": 200}
+        ]"#;
+        let rotation = format!(r#"{{"games": {}, "schedule": {{"EveryHands": 1}}}}"#, games);
+        let game = new_game((1, 2), "NoLimitHoldem", &rotation);
+        let game = game.act(Action::Fold).unwrap();
+        assert!(game.is_session_over());
+        assert!(game.get_possible_actions().is_empty());
+    }
+
+    #[test]
+    fn limit_bet_sizes() {
+        let game = new_game((1, 2), "LimitHoldem", "null");
+        // a small bet of 1 big blind before the turn
+        assert_eq!(game.get_raise_or_bet_range(), Some(2..=2));
+        assert!(game.act(Action::Raise(4)).is_err());
+        let game = game.act(Action::Raise(2)).unwrap();
+        assert_eq!(game.get_raise_or_bet_range(), Some(2..=2));
+        let game = game.act(Action::Call).unwrap();
+        assert_eq!(game.betting_round, BettingRound::Flop);
+        assert_eq!(game.get_raise_or_bet_range(), Some(2..=2));
+        let game = game.act(Action::Check).unwrap();
+        let game = game.act(Action::Check).unwrap();
+        // a big bet of 2 big blinds after
+        assert_eq!(game.betting_round, BettingRound::Turn);
+        assert_eq!(game.get_raise_or_bet_range(), Some(4..=4));
+        assert!(game.act(Action::Bet(2)).is_err());
+        let game = game.act(Action::Bet(4)).unwrap();
+        assert_eq!(game.get_raise_or_bet_range(), Some(4..=4));
+        let game = game.act(Action::Call).unwrap();
+        assert_eq!(game.betting_round, BettingRound::River);
+        assert_eq!(game.get_raise_or_bet_range(), Some(4..=4));
+
+        // no limit allows any raise from the big blind to all-in
+        let game = new_game((1, 2), "NoLimitHoldem", "null");
+        assert_eq!(game.get_raise_or_bet_range(), Some(2..=98));
+    }
+
+    #[test]
+    fn discard_hole_cards() {
+        // pineapple players discard after the betting before the flop
+        let game = new_game((1, 2), "Pineapple", "null");
+        assert_eq!(game.players[0].hole.len(), 3);
+        assert!(game.act(Action::Discard(0)).is_err());
+        let game = game.act(Action::Call).unwrap();
+        let game = game.act(Action::Check).unwrap();
+        assert!(game.discarding);
+        assert!(game.community.is_empty());
+        assert_eq!(
+            game.get_possible_actions(),
+            [Action::Discard(0), Action::Discard(1), Action::Discard(2)]
+        );
+        assert!(game.act(Action::Check).is_err());
+        assert!(game.act(Action::Bet(2)).is_err());
+        assert!(game.act(Action::Discard(3)).is_err());
+        let kept = [game.players[0].hole[0], game.players[0].hole[2]];
+        let game = game.act(Action::Discard(1)).unwrap();
+        assert_eq!(game.players[0].hole, kept);
+        assert_eq!(game.current_player_index, 1);
+        // the flop is dealt once everyone discarded
+        let game = game.act(Action::Discard(0)).unwrap();
+        assert!(!game.discarding);
+        assert_eq!(game.betting_round, BettingRound::Flop);
+        assert_eq!(game.community.len(), 3);
+
+        // check it down to a showdown of 2 card hands
+        let mut game = game;
+        for _ in 0..6 {
+            game = game.act(Action::Check).unwrap();
+        }
+        let showdown = game.last_showdown.as_ref().unwrap();
+        assert!(showdown.hands.iter().all(Option::is_some));
+        assert!(!showdown.winners_indices.is_empty());
+        assert_eq!(game.hands_played, 1);
+        assert_eq!(game.players[0].hole.len(), 3);
+
+        // crazy pineapple players discard after the betting on the flop
+        let game = new_game((1, 2), "CrazyPineapple", "null");
+        let game = game.act(Action::Call).unwrap();
+        let game = game.act(Action::Check).unwrap();
+        assert!(!game.discarding);
+        assert_eq!(game.community.len(), 3);
+        assert!(game.act(Action::Discard(0)).is_err());
+        let game = game.act(Action::Check).unwrap();
+        let game = game.act(Action::Check).unwrap();
+        assert!(game.discarding);
+        assert_eq!(game.community.len(), 3);
+        let game = game.act(Action::Discard(2)).unwrap();
+        let game = game.act(Action::Discard(2)).unwrap();
+        assert_eq!(game.betting_round, BettingRound::Turn);
+        assert_eq!(game.community.len(), 4);
+        assert!(game.players.iter().all(|player| player.hole.len() == 2));
+    }
+
+    #[test]
+    fn seat_equity() {
+        let cfg = EquityCfg {
+            samples: 2000,
+            seed: Some(1),
+            ..EquityCfg::default()
+        };
+        let mut game = new_game((1, 2), "NoLimitHoldem", "null");
+        game.players[0].hole = Card::parse_list("As Ah").unwrap();
+        let equity = game.get_seat_equity(0, cfg).unwrap();
+        assert!((equity.get_equity()[0] - 85.0).abs() < 3.0);
+        // the opponent's cards are unknown to the seat
+        game.players[1].hole = Card::parse_list("Kd Kc").unwrap();
+        assert_eq!(
+            game.get_seat_equity(0, cfg).unwrap().get_equity(),
+            equity.get_equity()
+        );
+        assert!(game.get_seat_equity(2, cfg).is_err());
+        let game = game.act(Action::Fold).unwrap();
+        assert!(game.get_seat_equity(0, cfg).is_ok());
+
+        // pineapple players need to discard first
+        let game = new_game((1, 2), "Pineapple", "null");
+        assert!(game.get_seat_equity(0, cfg).is_err());
+    }
+
+    #[test]
+    fn hide_bot_cards() {
+        let mut game = new_game((1, 2), "NoLimitHoldem", "null");
+        game.cfg.bots[1] = Some(BotCfg::CallingStation);
+        let cfg = EquityCfg {
+            samples: 100,
+            ..EquityCfg::default()
+        };
+        // the bot's cards aren't sent and nothing is told about them
+        assert_eq!(game.get_person_index(), Some(0));
+        let view = serde_json::to_value(game.get_view()).unwrap();
+        assert_eq!(view["players"][1]["hole"], serde_json::json!([null, null]));
+        assert!(view["players"][0]["hole"][0].is_object());
+        assert!(view.get("deck").is_none());
+        assert!(game.get_made_hands()[1].is_none());
+        assert!(game.get_seat_equity(1, cfg).is_err());
+        assert!(game.get_outs(1, None).is_err());
+
+        // until the showdown
+        game = game.act(Action::Call).unwrap();
+        while game.hands_played == 0 {
+            game = game.act(Action::Check).unwrap();
+        }
+        let showdown = game.get_view().last_showdown.unwrap();
+        assert!(showdown.hands[1].is_some());
+    }
+}
@@ -0,0 +1,299 @@
+//! Outs and draws of a hand on the flop and turn
+
+use serde::{Deserialize, Serialize};
+
+use super::card::{evaluate, Card, CardSet, HandType, Range};
+
+/// ranks of the lowest straight, 5-4-3-2-A
+const WHEEL: u16 = 0b1_0000_0000_1111;
+
+/// rank mask of every straight, from the wheel up
+fn straights() -> impl Iterator<Item = u16> {
+    std::iter::once(WHEEL).chain((0..9).map(|low| 0b11111 << low))
+}
+
+/// ranks of the cards, bit 0 is deuce and bit 12 is ace
+fn rank_mask(cards: CardSet) -> u16 {
+    (0..4).fold(0, |mask, suit| mask | cards.get_suit_mask(suit))
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Draw {
+    FlushDraw,             // four cards to a flush
+    OpenEndedStraightDraw, // two ranks complete a straight, including double gutshots
+    Gutshot,               // one rank completes a straight
+    BackdoorFlushDraw,     // three cards to a flush on the flop
+    BackdoorStraightDraw,  // three cards to a straight on the flop
+}
+
+/// what the outs improve the hand to
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub enum OutsTarget {
+    HandType(HandType),        // at least this hand type
+    Opponents(Vec<Vec<Card>>), // better than every one of the opponents' hands
+    Range(Range),              // better than most of the hands of the range, by weight
+}
+
+/// outs for the next card and the draws of a hand
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct Outs {
+    hand_type: HandType, // hand made with the current cards
+    outs: Vec<Card>,
+    draws: Vec<Draw>,
+    next_card: f64, // rule of 2, percentage of hitting an out on the next card
+    by_river: f64,  // rule of 4 on the flop, same as `next_card` on the turn
+}
+impl Outs {
+    pub fn get_hand_type(&self) -> HandType {
+        self.hand_type
+    }
+    pub fn get_outs(&self) -> &[Card] {
+        &self.outs
+    }
+    pub fn get_draws(&self) -> &[Draw] {
+        &self.draws
+    }
+    pub fn get_next_card(&self) -> f64 {
+        self.next_card
+    }
+    pub fn get_by_river(&self) -> f64 {
+        self.by_river
+    }
+}
+
+/// checks that there're 2 hole cards, that the board is a flop or a turn and that no card is
+/// used twice. returns all the cards that are known
+fn check_cards(hole: &[Card], board: &[Card], others: &[Vec<Card>]) -> Result<CardSet, String> {
+    // pineapple hands discard down to 2 first
+    if hole.len() != 2 {
+        return Err(format!("Outs need 2 hole cards, hand has {}", hole.len()));
+    }
+    if board.len() != 3 && board.len() != 4 {
+        return Err(format!(
+            "Outs are only for the flop and turn, board has {} cards",
+            board.len()
+        ));
+    }
+    let mut known = CardSet::new();
+    for &card in hole.iter().chain(board).chain(others.iter().flatten()) {
+        if !known.insert(card) {
+            return Err(format!("{} is used more than once", card));
+        }
+    }
+    Ok(known)
+}
+
+/// Cards that give the hand at least `target` on the next card.
+/// empty if the hand is already at least `target`
+pub fn get_outs_to(hole: &[Card], board: &[Card], target: HandType) -> Result<Vec<Card>, String> {
+    let known = check_cards(hole, board, &[])?;
+    if evaluate(known).get_hand_type() >= target {
+        return Ok(Vec::new());
+    }
+    Ok((!known)
+        .iter()
+        .filter(|&card| evaluate(known | card.into()).get_hand_type() >= target)
+        .collect())
+}
+
+/// Cards that make the hand better than every opponent's hand on the next card,
+/// taking the hand from behind or tied to ahead. empty if the hand is already ahead
+pub fn get_outs_against(
+    hole: &[Card],
+    board: &[Card],
+    opponents: &[Vec<Card>],
+) -> Result<Vec<Card>, String> {
+    let known = check_cards(hole, board, opponents)?;
+    let hole: CardSet = hole.iter().collect();
+    let board: CardSet = board.iter().collect();
+    let is_ahead = |board: CardSet| {
+        let strength = evaluate(hole | board);
+        opponents
+            .iter()
+            .all(|opponent| strength > evaluate(opponent.iter().collect::<CardSet>() | board))
+    };
+    if is_ahead(board) {
+        return Ok(Vec::new());
+    }
+    Ok((!known)
+        .iter()
+        .filter(|&card| is_ahead(board | card.into()))
+        .collect())
+}
+
+/// Cards that put the hand ahead of more than half of the range on the next card, for when
+/// the opponent's hand isn't known. empty if the hand is already ahead
+pub fn get_outs_against_range(
+    hole: &[Card],
+    board: &[Card],
+    range: &Range,
+) -> Result<Vec<Card>, String> {
+    let known = check_cards(hole, board, &[])?;
+    let hole: CardSet = hole.iter().collect();
+    let board: CardSet = board.iter().collect();
+    let range: Vec<(CardSet, f32)> = range
+        .iter()
+        .map(|(hand, weight)| (hand.iter().collect::<CardSet>(), weight))
+        .filter(|&(hand, _)| hand.is_disjoint(known))
+        .collect();
+    if range.is_empty() {
+        return Err("Range doesn't have any hands left after removing the known cards".into());
+    }
+    // weight of the hands the hand beats against the weight of the others,
+    // the hands that use the next card can't be dealt
+    let is_ahead = |board: CardSet| {
+        let strength = evaluate(hole | board);
+        let (mut beaten, mut total) = (0.0, 0.0);
+        for &(hand, weight) in range.iter().filter(|(hand, _)| hand.is_disjoint(board)) {
+            if strength > evaluate(hand | board) {
+                beaten += weight;
+            }
+            total += weight;
+        }
+        beaten > total / 2.0
+    };
+    if is_ahead(board) {
+        return Ok(Vec::new());
+    }
+    Ok((!known)
+        .iter()
+        .filter(|&card| is_ahead(board | card.into()))
+        .collect())
+}
+
+/// Flush and straight draws that use at least one of the hole cards.
+/// there's no straight draw if there's already a straight, same for flushes
+pub fn get_draws(hole: &[Card], board: &[Card]) -> Result<Vec<Draw>, String> {
+    check_cards(hole, board, &[])?;
+    let hole: CardSet = hole.iter().collect();
+    let board: CardSet = board.iter().collect();
+    let is_flop = board.len() == 3;
+    let mut draws = Vec::new();
+
+    // ====flush draws====
+    for suit in 0..4 {
+        if hole.get_suit_mask(suit) == 0 {
+            continue;
+        }
+        match (hole | board).get_suit_mask(suit).count_ones() {
+            4 => draws.push(Draw::FlushDraw),
+            3 if is_flop => draws.push(Draw::BackdoorFlushDraw),
+            _ => (),
+        }
+    }
+
+    // ====straight draws====
+    let ranks = rank_mask(hole | board);
+    let board_ranks = rank_mask(board);
+    if straights().any(|straight| ranks & straight == straight) {
+        return Ok(draws);
+    }
+    // ranks that complete a straight that the board alone doesn't make
+    let completing = (0..13)
+        .map(|rank| 1 << rank)
+        .filter(|&rank| {
+            ranks & rank == 0
+                && straights().any(|straight| {
+                    (ranks | rank) & straight == straight
+                        && (board_ranks | rank) & straight != straight
+                })
+        })
+        .count();
+    match completing {
+        0 => {
+            // three of the ranks of a straight, including a hole card's
+            let backdoor = straights().any(|straight| {
+                (ranks & straight).count_ones() >= 3 && ranks & straight & !board_ranks != 0
+            });
+            if is_flop && backdoor {
+                draws.push(Draw::BackdoorStraightDraw);
+            }
+        }
+        1 => draws.push(Draw::Gutshot),
+        _ => draws.push(Draw::OpenEndedStraightDraw),
+    }
+    Ok(draws)
+}
+
+/// outs of the hand to `target` on the next card, with its draws
+/// and the rule of 2 and 4 estimates of hitting an out
+pub fn get_outs(hole: &[Card], board: &[Card], target: &OutsTarget) -> Result<Outs, String> {
+    let outs = match target {
+        OutsTarget::HandType(hand_type) => get_outs_to(hole, board, *hand_type)?,
+        OutsTarget::Opponents(opponents) => get_outs_against(hole, board, opponents)?,
+        OutsTarget::Range(range) => get_outs_against_range(hole, board, range)?,
+    };
+    let next_card = (outs.len() as f64 * 2.0).min(100.0);
+    let by_river = if board.len() == 3 {
+        (outs.len() as f64 * 4.0).min(100.0)
+    } else {
+        next_card
+    };
+    Ok(Outs {
+        hand_type: evaluate(hole.iter().chain(board).collect()).get_hand_type(),
+        draws: get_draws(hole, board)?,
+        outs,
+        next_card,
+        by_river,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cards(s: &str) -> Vec<Card> {
+        Card::parse_list(s).unwrap()
+    }
+
+    #[test]
+    fn outs_and_draws() {
+        // nut flush draw with nine outs
+        let outs = get_outs(
+            &cards("Ah 9h"),
+            &cards("Kh 7h 2c"),
+            &OutsTarget::HandType(HandType::Flush),
+        )
+        .unwrap();
+        assert_eq!(outs.get_hand_type(), HandType::HighCard(1));
+        assert_eq!(outs.get_outs().len(), 9);
+        assert_eq!(outs.get_draws(), [Draw::FlushDraw]);
+        assert_eq!((outs.get_next_card(), outs.get_by_river()), (18.0, 36.0));
+
+        let straight = HandType::Straight(5);
+        let outs = get_outs_to(&cards("8c 9d"), &cards("Ts Jh 2c"), straight).unwrap();
+        assert_eq!(outs.len(), 8);
+        assert_eq!(
+            get_draws(&cards("8c 9d"), &cards("Ts Jh 2c")).unwrap(),
+            [Draw::OpenEndedStraightDraw]
+        );
+        assert_eq!(
+            get_draws(&cards("8c 9d"), &cards("Js Qh 2h 3s")).unwrap(),
+            [Draw::Gutshot]
+        );
+        assert_eq!(
+            get_draws(&cards("As 4d"), &cards("5c 9h Kh")).unwrap(),
+            [Draw::BackdoorStraightDraw]
+        );
+
+        // big slick against top pair needs to pair up
+        let outs =
+            get_outs_against(&cards("Ah Kh"), &cards("Qh 7c 2d"), &[cards("Qs Jd")]).unwrap();
+        assert_eq!(outs.len(), 6);
+
+        // against a random hand, a flush draw is behind until it hits and a set is ahead
+        let outs =
+            get_outs_against_range(&cards("8h 9h"), &cards("Kh 7h 2c"), &Range::full()).unwrap();
+        let hearts = outs.iter().filter(|card| card.to_string().ends_with('h'));
+        assert_eq!(hearts.count(), 9);
+        assert!(
+            get_outs_against_range(&cards("7c 7d"), &cards("Kh 7h 2c"), &Range::full())
+                .unwrap()
+                .is_empty()
+        );
+        assert!(get_outs_to(&cards("Ah Kh"), &cards("Ah 7c 2d"), straight).is_err());
+        assert!(get_outs_to(&cards("Ah Kh"), &cards("Qh 7c"), straight).is_err());
+        let target = OutsTarget::Range(Range::full());
+        assert!(get_outs(&cards("Ah Kh 2s"), &cards("Qh 7c 2d"), &target).is_err());
+    }
+}
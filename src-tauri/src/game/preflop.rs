@@ -0,0 +1,126 @@
+//! Heads up all-in equity between every pair of starting hand classes
+
+use std::{
+    fs::File,
+    io::{BufReader, BufWriter},
+    path::Path,
+};
+
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use super::card::{HandClass, NUM_CLASSES};
+use super::equity::{self, EquityCfg, Holding};
+
+/// version of the table file, bumped when the way the table is calculated changes
+const TABLE_VERSION: u32 = 1;
+
+/// Equity of each hand class against every other one, heads up and all-in preflop.
+/// A matchup is averaged over the combos of the two classes that don't share a card.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct PreflopTable {
+    version: u32,
+    samples: usize,   // boards sampled for each matchup
+    equity: Vec<f32>, // percentage for `hero index * NUM_CLASSES + villain index`
+}
+impl PreflopTable {
+    /// calculate the table by sampling boards for each matchup, in parallel
+    pub fn generate(samples: usize, seed: u64) -> PreflopTable {
+        // only one side of the diagonal, the other side is 100 minus it
+        let matchups: Vec<(usize, usize)> = (0..NUM_CLASSES)
+            .flat_map(|hero| (hero + 1..NUM_CLASSES).map(move |villain| (hero, villain)))
+            .collect();
+        let results: Vec<f64> = matchups
+            .par_iter()
+            .enumerate()
+            .map(|(index, &(hero, villain))| {
+                let holdings = [hero, villain]
+                    .map(|class| Holding::Range(HandClass::from_index(class).get_range()));
+                let cfg = EquityCfg {
+                    samples,
+                    seed: Some(seed.wrapping_add(index as u64)),
+                    ..EquityCfg::default()
+                };
+                equity::monte_carlo(&holdings, &[], &[], cfg)
+                    .expect("Different classes always have combos without conflicts")
+                    .get_equity()[0]
+            })
+            .collect();
+
+        // a class against itself is even
+        let mut equity = vec![50.0; NUM_CLASSES * NUM_CLASSES];
+        for (&(hero, villain), &hero_equity) in matchups.iter().zip(&results) {
+            equity[hero * NUM_CLASSES + villain] = hero_equity as f32;
+            equity[villain * NUM_CLASSES + hero] = (100.0 - hero_equity) as f32;
+        }
+        PreflopTable {
+            version: TABLE_VERSION,
+            samples,
+            equity,
+        }
+    }
+    /// equity of the hero's class against the villain's, in percentage
+    pub fn get_equity(&self, hero: HandClass, villain: HandClass) -> f32 {
+        self.equity[hero.get_index() * NUM_CLASSES + villain.get_index()]
+    }
+    /// equity of the hero's class against every class, in chart order
+    pub fn get_row(&self, hero: HandClass) -> &[f32] {
+        let start = hero.get_index() * NUM_CLASSES;
+        &self.equity[start..start + NUM_CLASSES]
+    }
+    pub fn get_samples(&self) -> usize {
+        self.samples
+    }
+    pub fn save(&self, path: &Path) -> Result<(), String> {
+        let file = File::create(path).map_err(|e| format!("Can't create {:?}: {}", path, e))?;
+        serde_json::to_writer(BufWriter::new(file), self)
+            .map_err(|e| format!("Can't write {:?}: {}", path, e))
+    }
+    pub fn load(path: &Path) -> Result<PreflopTable, String> {
+        let file = File::open(path).map_err(|e| format!("Can't read {:?}: {}", path, e))?;
+        let table: PreflopTable = serde_json::from_reader(BufReader::new(file))
+            .map_err(|e| format!("Can't parse {:?}: {}", path, e))?;
+        if table.version != TABLE_VERSION {
+            return Err(format!(
+                "{:?} is version {}, expected {}",
+                path, table.version, TABLE_VERSION
+            ));
+        }
+        if table.equity.len() != NUM_CLASSES * NUM_CLASSES {
+            return Err(format!("{:?} doesn't have every matchup", path));
+        }
+        Ok(table)
+    }
+    /// load the table cached at `path`, or generate it and cache it if it can't be loaded
+    pub fn load_or_generate(path: &Path, samples: usize) -> Result<PreflopTable, String> {
+        if let Ok(table) = PreflopTable::load(path) {
+            return Ok(table);
+        }
+        let table = PreflopTable::generate(samples, 0);
+        table.save(path)?;
+        Ok(table)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn preflop_table() {
+        let class = |s: &str| s.parse::<HandClass>().unwrap();
+        let table = PreflopTable::generate(20, 0);
+        assert!(table.get_equity(class("AA"), class("72o")) > 70.0);
+        assert_eq!(table.get_equity(class("T9s"), class("T9s")), 50.0);
+        let sum = table.get_equity(class("AKo"), class("QQ"))
+            + table.get_equity(class("QQ"), class("AKo"));
+        assert!((sum - 100.0).abs() < 1e-4);
+
+        let path = std::env::temp_dir().join("preflop_table_test.json");
+        table.save(&path).unwrap();
+        let loaded = PreflopTable::load_or_generate(&path, 1).unwrap();
+        assert_eq!(loaded.get_samples(), 20);
+        assert_eq!(loaded.get_row(class("AA")), table.get_row(class("AA")));
+        std::fs::remove_file(path).unwrap();
+    }
+}
@@ -0,0 +1,222 @@
+//! Kuhn poker and Leduc hold'em against a bot that plays the average strategy of CFR,
+//! for trying the solver in the app
+
+use rand::{distributions::WeightedIndex, prelude::Distribution, rngs::StdRng, SeedableRng};
+use serde::{Deserialize, Serialize};
+
+use super::card::Card;
+use super::cfr::{Cfr, CfrVariant, ExtensiveGame, Node};
+use super::kuhn::{Kuhn, KuhnState};
+use super::leduc::{Leduc, LeducState};
+
+/// a game tree that can be shown in the app
+pub trait SmallGame: ExtensiveGame {
+    /// the state from the person's seat, with the cards of both players
+    fn get_view(&self, state: &Self::State, person_index: usize) -> SmallGameView;
+}
+
+/// what the frontend shows of a hand
+#[derive(Serialize, Clone, Debug)]
+pub struct SmallGameView {
+    pub(super) person_index: usize,
+    pub(super) holes: Vec<Option<Card>>, // the bot's is None until the showdown
+    pub(super) community: Vec<Card>,
+    pub(super) history: Vec<String>, // eg. Check, Bet, Call
+    pub(super) actions: Vec<String>, // of the person if it's their turn
+    pub(super) put_in: Vec<f64>,     // chips each player has put in the pot
+    pub(super) showdown: bool,       // the hand ended without a fold
+    pub(super) payoff: Option<f64>,  // chips the person won, when the hand is over
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum SmallGameKind {
+    Kuhn,
+    Leduc,
+}
+
+#[derive(Clone, Debug)]
+enum SmallState {
+    Kuhn(KuhnState),
+    Leduc(LeducState),
+}
+
+/// what the frontend gets of a hand against a bot, the state stays with the bots
+#[derive(Serialize, Clone, Debug)]
+pub struct SmallHand {
+    id: u64, // the person acts in the hand with this id
+    view: SmallGameView,
+}
+impl SmallHand {
+    pub fn get_view(&self) -> &SmallGameView {
+        &self.view
+    }
+}
+
+/// samples an index by the weights
+fn sample(weights: &[f64], rng: &mut StdRng) -> usize {
+    WeightedIndex::new(weights).unwrap().sample(rng)
+}
+
+/// deals the cards and lets the bot act until it's the person's turn or the hand is over
+fn play<G: SmallGame>(
+    cfr: &Cfr<G>,
+    mut state: G::State,
+    person_index: usize,
+    rng: &mut StdRng,
+) -> (G::State, SmallGameView) {
+    let game = cfr.get_game();
+    loop {
+        let action = match game.get_node(&state) {
+            Node::Terminal => break,
+            Node::Player(player) if player == person_index => break,
+            Node::Chance => game.sample_chance(&state, rng),
+            Node::Player(_) => {
+                let actions = game.get_actions(&state);
+                let strategy = cfr
+                    .get_average_strategy(&game.get_info_set(&state))
+                    .unwrap_or_else(|| vec![1.0; actions.len()]);
+                actions[sample(&strategy, rng)].clone()
+            }
+        };
+        state = game.apply(&state, &action);
+    }
+    let mut view = game.get_view(&state, person_index);
+    if !view.showdown {
+        view.holes[1 - person_index] = None;
+    }
+    (state, view)
+}
+
+/// the person takes one of their actions, then the bot answers
+fn act<G: SmallGame>(
+    cfr: &Cfr<G>,
+    state: &G::State,
+    person_index: usize,
+    action_index: usize,
+    rng: &mut StdRng,
+) -> Result<(G::State, SmallGameView), String> {
+    let game = cfr.get_game();
+    if game.get_node(state) != Node::Player(person_index) {
+        return Err("It's not your turn".into());
+    }
+    let action = game
+        .get_actions(state)
+        .get(action_index)
+        .cloned()
+        .ok_or(format!("There's no action {}", action_index))?;
+    Ok(play(cfr, game.apply(state, &action), person_index, rng))
+}
+
+/// bots for both games, solved when they're created.
+/// they keep the state of the hand being played
+pub struct SmallBots {
+    kuhn: Cfr<Kuhn>,
+    leduc: Cfr<Leduc>,
+    rng: StdRng,
+    hand_id: u64,                              // counts the hands dealt
+    hand: Option<(SmallState, SmallGameView)>, // the hand with id `hand_id`
+}
+impl SmallBots {
+    /// runs `iterations` of discounted CFR on each game.
+    /// the cards are dealt from `seed`, random if None
+    pub fn train(iterations: usize, seed: Option<u64>) -> SmallBots {
+        let mut kuhn = Cfr::new(Kuhn, CfrVariant::default());
+        kuhn.iterate(iterations);
+        let mut leduc = Cfr::new(Leduc, CfrVariant::default());
+        leduc.iterate(iterations);
+        SmallBots {
+            kuhn,
+            leduc,
+            rng: match seed {
+                Some(seed) => StdRng::seed_from_u64(seed),
+                None => StdRng::from_entropy(),
+            },
+            hand_id: 0,
+            hand: None,
+        }
+    }
+
+    /// deals a new hand, the bot acts first if the person is the second player
+    pub fn new_hand(&mut self, kind: SmallGameKind, person_index: usize) -> SmallHand {
+        let (state, view) = match kind {
+            SmallGameKind::Kuhn => {
+                let (state, view) = play(&self.kuhn, Kuhn.get_root(), person_index, &mut self.rng);
+                (SmallState::Kuhn(state), view)
+            }
+            SmallGameKind::Leduc => {
+                let (state, view) =
+                    play(&self.leduc, Leduc.get_root(), person_index, &mut self.rng);
+                (SmallState::Leduc(state), view)
+            }
+        };
+        self.hand_id += 1;
+        self.hand = Some((state, view.clone()));
+        SmallHand {
+            id: self.hand_id,
+            view,
+        }
+    }
+
+    /// the person takes the action at `action_index` in the view of hand `hand_id`,
+    /// then the bot answers
+    pub fn act(&mut self, hand_id: u64, action_index: usize) -> Result<SmallHand, String> {
+        let (state, view) = match &self.hand {
+            Some(hand) if hand_id == self.hand_id => hand,
+            _ => return Err(format!("Hand {} isn't being played", hand_id)),
+        };
+        let person_index = view.person_index;
+        let (state, view) = match state {
+            SmallState::Kuhn(state) => {
+                let (state, view) =
+                    act(&self.kuhn, state, person_index, action_index, &mut self.rng)?;
+                (SmallState::Kuhn(state), view)
+            }
+            SmallState::Leduc(state) => {
+                let (state, view) = act(
+                    &self.leduc,
+                    state,
+                    person_index,
+                    action_index,
+                    &mut self.rng,
+                )?;
+                (SmallState::Leduc(state), view)
+            }
+        };
+        self.hand = Some((state, view.clone()));
+        Ok(SmallHand { id: hand_id, view })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn play_against_bots() {
+        let mut bots = SmallBots::train(100, Some(0));
+        for kind in [SmallGameKind::Kuhn, SmallGameKind::Leduc] {
+            for hand_index in 0..100 {
+                let person_index = hand_index % 2;
+                let mut hand = bots.new_hand(kind, person_index);
+                while hand.get_view().payoff.is_none() {
+                    let view = hand.get_view();
+                    // the bot's card is hidden, and so is the state with it
+                    let json = serde_json::to_value(&hand).unwrap();
+                    assert!(json["view"]["holes"][person_index].is_object());
+                    assert!(json["view"]["holes"][1 - person_index].is_null());
+                    assert_eq!(json.as_object().unwrap().len(), 2);
+                    assert!(bots.act(hand.id, view.actions.len()).is_err());
+                    assert!(bots.act(hand.id + 1, 0).is_err());
+                    hand = bots.act(hand.id, hand_index % view.actions.len()).unwrap();
+                }
+                let view = hand.get_view();
+                assert!(view.actions.is_empty());
+                assert!(bots.act(hand.id, 0).is_err());
+                assert_eq!(view.showdown, view.holes.iter().all(Option::is_some));
+                // the winner wins what the loser put in
+                let payoff = view.payoff.unwrap();
+                assert!(payoff == 0.0 || view.put_in.contains(&payoff.abs()));
+            }
+        }
+    }
+}
@@ -2,27 +2,73 @@
     all(not(debug_assertions), target_os = "windows"),
     windows_subsystem = "windows"
 )]
-mod game;
-use game::{Action, Game};
-use std::{fs::File, ops::RangeInclusive};
+use poker::game::{
+    agent::Table,
+    card::{BestHand, Card, HandClass, HandType, Range},
+    equity::{self, Equity, EquityCfg, RangeEquity},
+    outs::Outs,
+    preflop::PreflopTable,
+    small_game::{SmallBots, SmallGameKind, SmallHand},
+    Action, Game, GameView,
+};
+use std::{
+    fs::File,
+    ops::RangeInclusive,
+    path::Path,
+    sync::{Arc, Condvar, Mutex},
+    thread,
+};
+use tauri::{State, Window};
+
+/// preflop equity table, loaded on first use
+struct PreflopCache(Mutex<Option<PreflopTable>>);
+
+/// bots for Kuhn and Leduc with the hand being played, trained on first use
+struct SmallBotsCache(Mutex<Option<SmallBots>>);
+
+/// the game with the bots of poker.conf.json, set by get_new_game.
+/// the condvar wakes the thread that plays the bots, see `play_bots`
+struct BotTable(Arc<(Mutex<BotState>, Condvar)>);
+
+#[derive(Default)]
+struct BotState {
+    table: Option<Table>,
+    window: Option<Window>, // where the games the bots play are sent
+    generation: u64,        // counts the changes of the table from the commands
+}
 
 fn main() {
+    // a single thread plays the bots, so they never step the table at the same time
+    let bot_table = Arc::new((Mutex::new(BotState::default()), Condvar::new()));
+    let worker = bot_table.clone();
+    thread::spawn(move || play_bots(&worker));
     tauri::Builder::default()
+        .manage(PreflopCache(Mutex::new(None)))
+        .manage(BotTable(bot_table))
+        .manage(SmallBotsCache(Mutex::new(None)))
         .invoke_handler(tauri::generate_handler![
             get_new_game,
             get_possible_actions,
             get_call_amount,
             get_raise_or_bet_range,
-            act
+            act,
+            choose_next_game,
+            get_equity,
+            get_range_equity,
+            get_outs,
+            get_made_hands,
+            get_preflop_equity,
+            get_new_small_hand,
+            act_small_hand
         ])
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
 }
 
 #[tauri::command]
-fn get_new_game() -> Result<Game, String> {
+fn get_new_game(window: Window, bot_table: State<BotTable>) -> Result<GameView, String> {
     // Load in game config
-    match File::open("../poker.conf.json") {
+    let game = match File::open("../poker.conf.json") {
         Err(e) => Err(format!("Can't read poker.conf.json: {}", e)),
         // Parse the config
         Ok(file) => match serde_json::from_reader(file) {
@@ -30,25 +76,227 @@ fn get_new_game() -> Result<Game, String> {
             // Returns new game
             Ok(game_cfg) => Game::new(game_cfg),
         },
+    }?;
+    // seat the bots, they might have to act first
+    let (state, wake) = &*bot_table.0;
+    let mut state = state.lock().unwrap();
+    state.table = Some(Table::from_cfg(game.clone())?);
+    state.window = Some(window);
+    state.generation += 1;
+    wake.notify_one();
+    Ok(game.get_view())
+}
+
+/// Plays the bot seats whenever it's their turn, sending the view of the game to the frontend
+/// in a "game_updated" event after each action. runs for as long as the app, a step is dropped
+/// if a command changed the table while the bot was thinking
+fn play_bots(bot_table: &(Mutex<BotState>, Condvar)) {
+    let (state, wake) = bot_table;
+    let mut lock = state.lock().unwrap();
+    loop {
+        let think_delay = match &lock.table {
+            Some(table) if table.is_bot_turn() => table.get_think_delay(),
+            _ => {
+                lock = wake.wait(lock).unwrap();
+                continue;
+            }
+        };
+        // think without holding the lock
+        let generation = lock.generation;
+        drop(lock);
+        thread::sleep(think_delay);
+        lock = state.lock().unwrap();
+        if lock.generation != generation {
+            continue;
+        }
+        let BotState { table, window, .. } = &mut *lock;
+        match table.as_mut().unwrap().step() {
+            Ok(_) => {
+                let view = table.as_ref().unwrap().get_game().get_view();
+                if let Some(Err(e)) = window.as_ref().map(|w| w.emit("game_updated", view)) {
+                    eprintln!("Can't send the game to the frontend: {}", e);
+                }
+            }
+            Err(e) => {
+                eprintln!("Bot picked an illegal action: {}", e);
+                // wait for a new game
+                lock = wake
+                    .wait_while(lock, |state| state.generation == generation)
+                    .unwrap();
+            }
+        }
+    }
+}
+
+/// a copy of the game being played, the app only gets its view
+fn get_game(bot_table: &BotTable) -> Result<Game, String> {
+    let (state, _) = &*bot_table.0;
+    match &state.lock().unwrap().table {
+        Some(table) => Ok(table.get_game().clone()),
+        None => Err("No game is being played, start one with get_new_game".into()),
+    }
+}
+
+#[tauri::command]
+fn get_possible_actions(bot_table: State<BotTable>) -> Result<Vec<Action>, String> {
+    Ok(get_game(&bot_table)?.get_possible_actions())
+}
+
+#[tauri::command]
+fn get_call_amount(bot_table: State<BotTable>) -> Result<Option<usize>, String> {
+    Ok(get_game(&bot_table)?.get_call_amount())
+}
+
+#[tauri::command]
+fn get_raise_or_bet_range(
+    bot_table: State<BotTable>,
+) -> Result<Option<RangeInclusive<usize>>, String> {
+    Ok(get_game(&bot_table)?.get_raise_or_bet_range())
+}
+
+/// the table if `progress` is the progress of the game being played on it,
+/// so people don't act on a game that changed since they saw it
+fn get_table(state: &mut BotState, progress: (usize, usize)) -> Result<&mut Table, String> {
+    let table = state
+        .table
+        .as_mut()
+        .ok_or("No game is being played, start one with get_new_game")?;
+    if progress != table.get_game().get_progress() {
+        return Err("The bots acted since, wait for the new game".into());
+    }
+    Ok(table)
+}
+
+/// acts for the person in the current seat, then lets the bots answer in the background.
+/// `progress` is the one of the view the person acted on, see `Game::get_progress`
+#[tauri::command]
+fn act(
+    progress: (usize, usize),
+    action: Action,
+    bot_table: State<BotTable>,
+) -> Result<GameView, String> {
+    let (state, wake) = &*bot_table.0;
+    let mut state = state.lock().unwrap();
+    let table = get_table(&mut state, progress)?;
+    if table.is_bot_turn() {
+        return Err("Waiting for the bot to act".into());
     }
+    table.act(action)?;
+    let view = table.get_game().get_view();
+    state.generation += 1;
+    wake.notify_one();
+    Ok(view)
 }
 
+/// the person picks the game of the next orbit when they deal, bot dealers keep the current one
 #[tauri::command]
-fn get_possible_actions(game: Game) -> Vec<Action> {
-    game.get_possible_actions()
+fn choose_next_game(
+    progress: (usize, usize),
+    game_index: usize,
+    bot_table: State<BotTable>,
+) -> Result<GameView, String> {
+    let (state, wake) = &*bot_table.0;
+    let mut state = state.lock().unwrap();
+    let table = get_table(&mut state, progress)?;
+    let dealer_index = table.get_game().get_dealer_index();
+    if table.get_game().is_bot_seat(dealer_index) {
+        return Err("The bot is the dealer, it keeps the current game".into());
+    }
+    let new_game = table
+        .get_game()
+        .choose_next_game(dealer_index, game_index)?;
+    let view = new_game.get_view();
+    table.set_game(new_game);
+    state.generation += 1;
+    wake.notify_one();
+    Ok(view)
 }
 
+/// equity of the person against random hands, they don't know the others' cards
 #[tauri::command]
-fn get_call_amount(game: Game) -> Option<usize> {
-    game.get_call_amount()
+fn get_equity(samples: usize, bot_table: State<BotTable>) -> Result<Equity, String> {
+    let game = get_game(&bot_table)?;
+    let person_index = game.get_person_index().ok_or("The bots play every seat")?;
+    game.get_seat_equity(
+        person_index,
+        EquityCfg {
+            samples,
+            ..EquityCfg::default()
+        },
+    )
 }
 
+/// the current hand of each person, null if folded and for the bots
 #[tauri::command]
-fn get_raise_or_bet_range(game: Game) -> Option<RangeInclusive<usize>> {
-    game.get_raise_or_bet_range()
+fn get_made_hands(bot_table: State<BotTable>) -> Result<Vec<Option<BestHand>>, String> {
+    Ok(get_game(&bot_table)?.get_made_hands())
 }
 
+/// outs of the person to `target`, or to get ahead of a random hand if there's no target
 #[tauri::command]
-fn act(game: Game, action: Action) -> Result<Game, String> {
-    game.act(action)
+fn get_outs(target: Option<HandType>, bot_table: State<BotTable>) -> Result<Outs, String> {
+    let game = get_game(&bot_table)?;
+    let person_index = game.get_person_index().ok_or("The bots play every seat")?;
+    game.get_outs(person_index, target)
+}
+
+/// equity of ranges in range notation against each other, the first one is the hero's.
+/// `samples` is the number of boards, split between the hands of the hero.
+/// async so it runs off the main thread, many samples take a while
+#[tauri::command(async)]
+fn get_range_equity(
+    ranges: Vec<Range>,
+    board: String,
+    samples: usize,
+) -> Result<RangeEquity, String> {
+    let board = Card::parse_list(&board).map_err(|e| e.to_string())?;
+    equity::range_vs_range(
+        &ranges,
+        &board,
+        &[],
+        EquityCfg {
+            samples,
+            ..EquityCfg::default()
+        },
+    )
+}
+
+/// heads up all-in equity of a starting hand against every other one, in hand chart order.
+/// the table is generated the first time and cached next to poker.conf.json.
+/// async so the window doesn't freeze while it's generated
+#[tauri::command(async)]
+fn get_preflop_equity(hand: HandClass, cache: State<PreflopCache>) -> Result<Vec<f32>, String> {
+    let mut table = cache.0.lock().unwrap();
+    if table.is_none() {
+        *table = Some(PreflopTable::load_or_generate(
+            Path::new("../preflop_equity.json"),
+            10_000,
+        )?);
+    }
+    Ok(table.as_ref().unwrap().get_row(hand).to_vec())
+}
+
+/// deals a hand of Kuhn or Leduc against a bot, which acts first if the person is the second player.
+/// async so training the bots doesn't block the main thread
+#[tauri::command(async)]
+fn get_new_small_hand(
+    kind: SmallGameKind,
+    person_index: usize,
+    cache: State<SmallBotsCache>,
+) -> SmallHand {
+    let mut bots = cache.0.lock().unwrap();
+    bots.get_or_insert_with(|| SmallBots::train(1000, None))
+        .new_hand(kind, person_index)
+}
+
+/// the person takes one of the actions of the hand, then the bot answers
+#[tauri::command(async)]
+fn act_small_hand(
+    hand_id: u64,
+    action_index: usize,
+    cache: State<SmallBotsCache>,
+) -> Result<SmallHand, String> {
+    let mut bots = cache.0.lock().unwrap();
+    bots.get_or_insert_with(|| SmallBots::train(1000, None))
+        .act(hand_id, action_index)
 }
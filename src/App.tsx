@@ -2,40 +2,96 @@ import "./App.css";
 import React from "react";
 import Community from "./Community";
 import { invoke } from '@tauri-apps/api/tauri'
+import { listen, UnlistenFn } from '@tauri-apps/api/event'
 import Action from "./Action";
-import Player, { IPlayerProps, NULL_PLAYER } from "./Player"
-import { ICardProps } from "./Card";
+import Player, { NULL_PLAYER } from "./Player"
+import { ICardProps, NULL_CARD } from "./Card";
+import Showdown, { IBestHand, IShowdown } from "./Showdown";
 
 export interface IAppProps {
 }
 
+export interface PlayerView {
+  name: string,
+  hole: (ICardProps | null)[], // null for the cards of a bot
+  bet_size: number,
+  stack: number,
+}
+// the game as the people at the table see it, the backend keeps the game itself
 export interface Game {
-  players: IPlayerProps[],
+  players: PlayerView[],
+  bots: boolean[], // seats played by a bot
   community: ICardProps[],
+  dealer_index: number,
   pot_size: number,
+  current_player_index: number,
+  discarding: boolean,
+  last_showdown: IShowdown | null,
+  hands_played: number,
+  session_over: boolean,
+  history: object[], // actions of the current hand
 }
 export interface NumRange {
   start: number,
   end: number,
 }
+export interface Equity {
+  win: number[],
+  tie: number[],
+  equity: number[],
+  samples: number,
+}
+export interface Outs {
+  outs: ICardProps[],
+  draws: string[],
+  next_card: number,
+  by_river: number,
+}
 export interface IAppState {
   game: Game,
+  equity: Equity | null,
+  outs: Outs | null,
+  made_hands: (IBestHand | null)[],
   possible_actions: string[],
   call_amount: number,
   raise_or_bet_range: NumRange,
 }
 
+// seat of the person looking at the table, the current player if it's a person,
+// otherwise the first seat that isn't a bot. -1 if the bots play every seat.
+// the backend picks the same seat for the equity and the outs
+function get_hero_index(game: Game) {
+  if (!game.bots[game.current_player_index]) {
+    return game.current_player_index;
+  }
+  return game.bots.indexOf(false);
+}
 
 export default class App extends React.Component<IAppProps, IAppState> {
+  unlisten: Promise<UnlistenFn> | null = null;
+  // last game shown, this.state.game can lag behind it
+  latest_game: Game | null = null;
+
   constructor(props: IAppProps) {
     super(props);
 
     this.state = {
       game: {
         players: [NULL_PLAYER, NULL_PLAYER],
+        bots: [false, false],
         community: [],
+        dealer_index: 0,
         pot_size: 0,
+        current_player_index: 0,
+        discarding: false,
+        last_showdown: null,
+        hands_played: 0,
+        session_over: false,
+        history: [],
       },
+      equity: null,
+      outs: null,
+      made_hands: [],
       possible_actions: [],
       call_amount: 0,
       raise_or_bet_range: {
@@ -44,13 +100,25 @@ export default class App extends React.Component<IAppProps, IAppState> {
     }
   }
   componentDidMount(): void {
+    // the backend sends the game after every action of a bot
+    this.unlisten = listen('game_updated', (event) => this.updateGame(event.payload as Game));
     invoke('get_new_game').then((game) => this.updateGame(game as Game));
   }
+  componentWillUnmount(): void {
+    this.unlisten?.then((unlisten) => unlisten());
+  }
 
   public render() {
     return (
       <div className="app">
-        <Player {...this.state.game.players[1]} />
+        <Showdown
+          showdown={this.state.game.last_showdown}
+          player_names={this.state.game.players.map((player) => player.name)}
+        />
+        <Player {...this.state.game.players[1]} hole={this.get_hole(1)}
+          on_discard={this.discard_handler(1)}
+          equity={this.get_player_equity(1)} outs={this.get_player_outs(1)}
+          made_hand={this.state.made_hands[1]?.description} />
         <Community
           cards={this.state.game.community}
           pot={this.state.game.pot_size}
@@ -58,7 +126,10 @@ export default class App extends React.Component<IAppProps, IAppState> {
             this.state.game.players.reduce((acc, player) => acc + player.bet_size, 0)
           }
         />
-        <Player {...this.state.game.players[0]} />
+        <Player {...this.state.game.players[0]} hole={this.get_hole(0)}
+          on_discard={this.discard_handler(0)}
+          equity={this.get_player_equity(0)} outs={this.get_player_outs(0)}
+          made_hand={this.state.made_hands[0]?.description} />
         <Action
           possible_actions={this.state.possible_actions}
           on_call={() => this.on_call()}
@@ -74,13 +145,38 @@ export default class App extends React.Component<IAppProps, IAppState> {
   }
 
   updateGame(game: Game) {
-    invoke('get_possible_actions', { game: game }).then(
-      (possible_actions) => this.setState({ possible_actions: possible_actions as string[] })
+    // the bots' games can arrive before the answer to the person's action, keep the newest
+    const latest = this.latest_game;
+    if (latest !== null && (game.hands_played < latest.hands_played
+      || (game.hands_played === latest.hands_played && game.history.length < latest.history.length))) {
+      return;
+    }
+    this.latest_game = game;
+    // the backend answers for the game it keeps, which is this one or a newer one.
+    // people can't act for the bots
+    const bot_turn = game.bots[game.current_player_index];
+    invoke('get_possible_actions').then(
+      (possible_actions) => this.setState({
+        possible_actions: bot_turn ? [] : possible_actions as string[]
+      })
     );
-    invoke('get_call_amount', { game: game }).then(
+    invoke('get_call_amount').then(
       (call_amount) => this.setState({ call_amount: call_amount as number })
     );
-    invoke('get_raise_or_bet_range', { game: game }).then(
+    // live equity and outs of the person against random hands, not available while discarding
+    invoke('get_equity', { samples: 10000 }).then(
+      (equity) => this.setState({ equity: equity as Equity }),
+      () => this.setState({ equity: null })
+    );
+    invoke('get_made_hands').then(
+      (made_hands) => this.setState({ made_hands: made_hands as (IBestHand | null)[] })
+    );
+    // only on the flop and turn
+    invoke('get_outs', { target: null }).then(
+      (outs) => this.setState({ outs: outs as Outs }),
+      () => this.setState({ outs: null })
+    );
+    invoke('get_raise_or_bet_range').then(
       (raise_range) => {
         console.log(raise_range);
         this.setState({ raise_or_bet_range: raise_range as NumRange });
@@ -90,29 +186,64 @@ export default class App extends React.Component<IAppProps, IAppState> {
     this.setState({ game: game });
   }
 
+  // the bots' cards aren't sent and stay face down, the showdown shows them
+  get_hole(player_index: number) {
+    return this.state.game.players[player_index].hole.map((card) => card === null ? NULL_CARD : card);
+  }
+
+  // the person's equity is first, the others are random hands
+  get_player_equity(player_index: number) {
+    if (this.state.equity === null || get_hero_index(this.state.game) !== player_index) {
+      return undefined;
+    }
+    return this.state.equity.equity[0];
+  }
+
+  // outs with the rule of 2 and 4 estimates, for the person
+  get_player_outs(player_index: number) {
+    const outs = this.state.outs;
+    if (outs === null || get_hero_index(this.state.game) !== player_index) {
+      return undefined;
+    }
+    // eg. "OpenEndedStraightDraw" to "Open Ended Straight Draw"
+    const draws = outs.draws.map((draw) => draw.replace(/([a-z])([A-Z])/g, '$1 $2'));
+    return ["Outs: " + outs.outs.length + " (" + outs.next_card + "% / " + outs.by_river + "%)"]
+      .concat(draws)
+      .join(", ");
+  }
+
+  // only the current player can discard, and only while discarding
+  discard_handler(player_index: number) {
+    if (!this.state.game.discarding || this.state.game.current_player_index !== player_index
+      || this.state.game.bots[player_index]) {
+      return undefined;
+    }
+    return (index: number) => this.on_discard(index);
+  }
+
+  // the progress tells the backend which game the person acted on
+  act(action: string | object) {
+    const game = this.state.game;
+    invoke("act", { progress: [game.hands_played, game.history.length], action: action }).then(
+      (game) => this.updateGame(game as Game)
+    );
+  }
   on_call() {
-    invoke("act", { game: this.state.game, action: "Call" }).then((game) =>
-      this.updateGame(game as Game)
-    )
+    this.act("Call");
   }
   on_bet(amount: number) {
-    invoke("act", { game: this.state.game, action: { Bet: amount } }).then((game) =>
-    this.updateGame(game as Game)
-    )
+    this.act({ Bet: amount });
   }
   on_raise(amount: number) {
-    invoke("act", { game: this.state.game, action: { Raise: amount } }).then((game) => {
-      this.updateGame(game as Game)
-    })
+    this.act({ Raise: amount });
   }
   on_check() {
-    invoke("act", { game: this.state.game, action: "Check" }).then((game) =>
-      this.updateGame(game as Game)
-    )
+    this.act("Check");
   }
   on_fold() {
-    invoke("act", { game: this.state.game, action: "Fold" }).then((game) =>
-      this.updateGame(game as Game)
-    )
+    this.act("Fold");
+  }
+  on_discard(index: number) {
+    this.act({ Discard: index });
   }
-}
\ No newline at end of file
+}
@@ -4,9 +4,16 @@ import Card, { ICardProps, NULL_CARD } from './Card';
 
 export interface IPlayerProps {
     name: string;
-    hole: [ICardProps, ICardProps];
+    hole: ICardProps[];
     bet_size: number;
     stack: number;
+    // set when the player has to discard one of the hole cards
+    on_discard?: (index: number) => void;
+    equity?: number;
+    // eg. "Two Pair, Kings and Threes, Five kicker"
+    made_hand?: string;
+    // eg. "Outs: 9 (18% / 36%), Flush Draw"
+    outs?: string;
 }
 
 export const NULL_PLAYER: IPlayerProps = { name: "", hole: [NULL_CARD, NULL_CARD], bet_size: 0, stack: 0 }
@@ -19,11 +26,25 @@ export default function Player(props: IPlayerProps) {
                 <span>{props.name}</span>
                 <span>{"Bet: " + props.bet_size}</span>
                 <span>{"Stack: " + props.stack}</span>
+                {props.equity === undefined
+                    ? <></>
+                    : <span>{"Equity: " + props.equity.toFixed(1) + "%"}</span>}
+                {props.made_hand === undefined ? <></> : <span>{props.made_hand}</span>}
+                {props.outs === undefined ? <></> : <span>{props.outs}</span>}
 
             </div>
             <div className='cardList' >
-                <Card {...props.hole[0]} />
-                <Card {...props.hole[1]} />
+                {props.hole.map((card, index) => (
+                    props.on_discard === undefined
+                        ? <Card key={index} {...card} />
+                        : <div
+                            key={index}
+                            className='discardable'
+                            onClick={() => props.on_discard!(index)}
+                        >
+                            <Card {...card} />
+                        </div>
+                ))}
             </div>
         </div>
     )
@@ -0,0 +1,56 @@
+import * as React from 'react';
+import Card, { ICardProps } from './Card'
+
+export interface IBestHand {
+  cards: ICardProps[];
+  hole_used: ICardProps[];
+  description: string;
+}
+
+export interface IShowdown {
+  hands: (IBestHand | null)[];
+  winners_indices: number[];
+}
+
+export interface IShowdownProps {
+  showdown: IShowdown | null;
+  player_names: string[];
+}
+
+function is_same_card(a: ICardProps, b: ICardProps) {
+  return a.rank === b.rank && a.suit === b.suit;
+}
+
+// shows the five cards each player played in the previous hand,
+// the hole cards that played are highlighted
+export default function Showdown(props: IShowdownProps) {
+  if (props.showdown === null) {
+    return (<></>);
+  }
+  const showdown = props.showdown;
+  return (
+    <div className='showdown'>
+      {showdown.hands.map((hand, index) => hand === null ? null : (
+        <div key={index} className='showdownHand'>
+          <span>
+            {props.player_names[index]}
+            {showdown.winners_indices.includes(index) ? " (WINNER)" : ""}
+            {": " + hand.description}
+          </span>
+          <div className='cardList'>
+            {hand.cards.map((card, card_index) => (
+              <div
+                key={card_index}
+                className={hand.hole_used.some((hole_card) => is_same_card(hole_card, card))
+                  ? 'holeUsed'
+                  : ''}
+              >
+                <Card {...card} />
+              </div>
+            ))}
+          </div>
+        </div>
+      ))}
+    </div>
+  );
+}
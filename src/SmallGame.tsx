@@ -0,0 +1,103 @@
+import * as React from 'react';
+import { invoke } from '@tauri-apps/api/tauri'
+import Card, { ICardProps, NULL_CARD } from './Card'
+
+export interface SmallGameView {
+  person_index: number,
+  holes: (ICardProps | null)[], // the bot's card is null until the showdown
+  community: ICardProps[],
+  history: string[],
+  actions: string[],
+  put_in: number[],
+  showdown: boolean,
+  payoff: number | null,
+}
+export interface SmallHand {
+  id: number, // the backend keeps the state of the hand with this id
+  view: SmallGameView,
+}
+export interface ISmallGameProps {
+  kind: "Kuhn" | "Leduc",
+}
+
+// Kuhn poker or Leduc hold'em against a bot trained with CFR,
+// the person alternates between acting first and second
+export default function SmallGame(props: ISmallGameProps) {
+  let [hand, set_hand] = React.useState<SmallHand | null>(null);
+  let [hands_played, set_hands_played] = React.useState(0);
+  let [winnings, set_winnings] = React.useState(0);
+
+  const new_hand = (person_index: number) => {
+    invoke('get_new_small_hand', { kind: props.kind, personIndex: person_index }).then(
+      (hand) => set_hand(hand as SmallHand)
+    );
+  };
+  // start over when the game changes
+  React.useEffect(() => {
+    set_hands_played(0);
+    set_winnings(0);
+    new_hand(0);
+  }, [props.kind]);
+
+  const act = (action_index: number) => {
+    invoke('act_small_hand', { handId: hand!.id, actionIndex: action_index }).then((new_hand) => {
+      const payoff = (new_hand as SmallHand).view.payoff;
+      if (payoff !== null) {
+        set_hands_played(hands_played + 1);
+        set_winnings(winnings + payoff);
+      }
+      set_hand(new_hand as SmallHand);
+    });
+  };
+
+  if (hand === null) {
+    return (<div className='app'>Training the bot...</div>);
+  }
+  const view = hand.view;
+  const bot_index = 1 - view.person_index;
+  const pot = view.put_in.reduce((acc, chips) => acc + chips, 0);
+  return (
+    <div className='app'>
+      <span className='smallGameInfo'>
+        {props.kind}: {winnings >= 0 ? "+" : ""}{winnings} over {hands_played} hands
+      </span>
+      <div className='cardList'>
+        <Card {...(view.holes[bot_index] ?? NULL_CARD)} />
+      </div>
+      <div className='community'>
+        <span>POT SIZE: {pot}</span>
+        <div className='cardList'>
+          {view.community.map((card, index) => (
+            <Card key={index} {...card} />
+          ))}
+        </div>
+      </div>
+      <span className='smallGameInfo'>{view.history.join(", ")}</span>
+      <div className='cardList'>
+        <Card {...(view.holes[view.person_index] ?? NULL_CARD)} />
+      </div>
+      {view.payoff === null
+        ? <div className='actions'>
+          {view.actions.map((action, index) => (
+            <button
+              key={index}
+              className={action === "Fold" ? 'red' : 'green'}
+              onClick={() => act(index)}
+            >
+              {action}
+            </button>
+          ))}
+        </div>
+        : <div className='actions'>
+          <span className='smallGameInfo'>
+            {view.payoff > 0 ? "You won " + view.payoff
+              : view.payoff < 0 ? "You lost " + -view.payoff : "Split pot"}
+          </span>
+          <button className='white' onClick={() => new_hand(1 - view.person_index)}>
+            Next hand
+          </button>
+        </div>
+      }
+    </div>
+  );
+}
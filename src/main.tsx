@@ -1,10 +1,30 @@
 import React from "react";
 import ReactDOM from "react-dom/client";
 import App from "./App";
+import SmallGame from "./SmallGame";
 import "./style.css";
 
+type Mode = "Holdem" | "Kuhn" | "Leduc";
+
+// hold'em, or one of the small games against a trained bot
+function Root() {
+  let [mode, set_mode] = React.useState<Mode>("Holdem");
+  return (
+    <>
+      <div className='modes'>
+        {(["Holdem", "Kuhn", "Leduc"] as Mode[]).map((m) => (
+          <button key={m} className={m === mode ? 'green' : 'white'} onClick={() => set_mode(m)}>
+            {m === "Holdem" ? "Hold'em" : m}
+          </button>
+        ))}
+      </div>
+      {mode === "Holdem" ? <App /> : <SmallGame kind={mode} />}
+    </>
+  );
+}
+
 ReactDOM.createRoot(document.getElementById("root") as HTMLElement).render(
   <React.StrictMode>
-    <App />
+    <Root />
   </React.StrictMode>
 );